@@ -1,65 +1,257 @@
+/// 1bpp canvas. Each row is a run of `u64` words, MSB = leftmost pixel, so
+/// spans can be filled a word at a time and rows pack straight into the
+/// device's MSB-first byte order. Bits past `width` are always zero.
 pub struct Canvas {
     width: usize,
     height: usize,
-    pixels: Vec<u8>,
+    // u64 words per row
+    stride: usize,
+    words: Vec<u64>,
+}
+
+/// Horizontal placement for `draw_text_scaled_aligned`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Shading for `rect_fill_pattern`. Patterns are anchored to canvas
+/// coordinates, so neighbouring fills line up.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    /// Every other pixel, offset per row (50%).
+    Checker,
+    /// One pixel in each 2x2 block (25%).
+    Dots25,
+    /// Every other row.
+    HLines,
+    /// Every other column.
+    VLines,
+}
+
+impl Pattern {
+    pub fn lit(self, x: i32, y: i32) -> bool {
+        match self {
+            Pattern::Checker => (x + y).rem_euclid(2) == 0,
+            Pattern::Dots25 => x.rem_euclid(2) == 0 && y.rem_euclid(2) == 0,
+            Pattern::HLines => y.rem_euclid(2) == 0,
+            Pattern::VLines => x.rem_euclid(2) == 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SpanOp {
+    Set,
+    Clear,
+    Toggle,
 }
 
 impl Canvas {
     pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
         Self {
             width,
             height,
-            pixels: vec![0; width * height],
+            stride,
+            words: vec![0; stride * height],
         }
     }
 
+    /// (width, height) in pixels.
+    #[allow(dead_code)]
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
     pub fn clear(&mut self, on: bool) {
-        self.pixels.fill(if on { 1 } else { 0 });
+        if !on {
+            self.words.fill(0);
+            return;
+        }
+        for y in 0..self.height {
+            self.apply_span(y, 0, self.width, SpanOp::Set);
+        }
     }
 
-    pub fn set(&mut self, x: i32, y: i32, on: bool) {
-        if x < 0 || y < 0 {
+    /// Moves the whole image by (`dx`, `dy`) pixels. Pixels pushed past an edge
+    /// are dropped and the uncovered strip is left dark.
+    pub fn shift(&mut self, dx: i32, dy: i32) {
+        let stride = self.stride;
+        if dx.unsigned_abs() as usize >= self.width || dy.unsigned_abs() as usize >= self.height {
+            self.words.fill(0);
             return;
         }
-        let ux = x as usize;
-        let uy = y as usize;
-        if ux >= self.width || uy >= self.height {
+
+        let rows = dy.unsigned_abs() as usize * stride;
+        let len = self.words.len();
+        if dy > 0 {
+            self.words.copy_within(..len - rows, rows);
+            self.words[..rows].fill(0);
+        } else if dy < 0 {
+            self.words.copy_within(rows.., 0);
+            self.words[len - rows..].fill(0);
+        }
+
+        if dx == 0 {
             return;
         }
-        self.pixels[uy * self.width + ux] = u8::from(on);
+        let n = dx.unsigned_abs() as usize;
+        let (word_shift, bit_shift) = (n / 64, (n % 64) as u32);
+        // Bits past `width` must stay zero after a shift to the right.
+        let tail_mask = match self.width % 64 {
+            0 => !0,
+            used => !0u64 << (64 - used),
+        };
+        for row in self.words.chunks_exact_mut(stride) {
+            // MSB is the leftmost pixel, so moving right shifts towards the LSB.
+            // Walk in the direction that reads each source word before it's overwritten.
+            if dx > 0 {
+                for i in (0..stride).rev() {
+                    let hi = i.checked_sub(word_shift).map_or(0, |j| row[j]);
+                    let lo = i.checked_sub(word_shift + 1).map_or(0, |j| row[j]);
+                    row[i] = if bit_shift == 0 {
+                        hi
+                    } else {
+                        (hi >> bit_shift) | (lo << (64 - bit_shift))
+                    };
+                }
+            } else {
+                for i in 0..stride {
+                    let hi = row.get(i + word_shift).copied().unwrap_or(0);
+                    let lo = row.get(i + word_shift + 1).copied().unwrap_or(0);
+                    row[i] = if bit_shift == 0 {
+                        hi
+                    } else {
+                        (hi << bit_shift) | (lo >> (64 - bit_shift))
+                    };
+                }
+            }
+            row[stride - 1] &= tail_mask;
+        }
     }
 
-    pub fn get(&self, x: i32, y: i32) -> bool {
+    /// Mirrors the image left to right in place.
+    pub fn flip_horizontal(&mut self) {
+        let stride = self.stride;
+        // Reversing a row's words and their bits mirrors all `stride * 64`
+        // bits; moving left by the unused tail puts pixel 0 back at the MSB.
+        let pad = (stride * 64 - self.width) as u32;
+        for row in self.words.chunks_exact_mut(stride) {
+            row.reverse();
+            for word in row.iter_mut() {
+                *word = word.reverse_bits();
+            }
+            if pad > 0 {
+                for i in 0..stride {
+                    let lo = row.get(i + 1).copied().unwrap_or(0);
+                    row[i] = (row[i] << pad) | (lo >> (64 - pad));
+                }
+            }
+        }
+    }
+
+    /// Mirrors the image top to bottom in place.
+    pub fn flip_vertical(&mut self) {
+        let stride = self.stride;
+        for y in 0..self.height / 2 {
+            let (top, bottom) = self.words.split_at_mut((self.height - 1 - y) * stride);
+            top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+    }
+
+    /// Turns the image upside down, e.g. for a keyboard mounted rotated.
+    pub fn rotate_180(&mut self) {
+        self.flip_horizontal();
+        self.flip_vertical();
+    }
+
+    /// Word index and bit mask of an in-bounds pixel; `None` when clipped.
+    fn bit(&self, x: i32, y: i32) -> Option<(usize, u64)> {
         if x < 0 || y < 0 {
-            return false;
+            return None;
         }
         let ux = x as usize;
         let uy = y as usize;
         if ux >= self.width || uy >= self.height {
-            return false;
+            return None;
+        }
+        Some((uy * self.stride + ux / 64, 1u64 << (63 - ux % 64)))
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, on: bool) {
+        if let Some((word, mask)) = self.bit(x, y) {
+            if on {
+                self.words[word] |= mask;
+            } else {
+                self.words[word] &= !mask;
+            }
+        }
+    }
+
+    /// Reads one pixel back. Clipping mirrors `set`: anything outside the
+    /// canvas reads as unlit.
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        self.bit(x, y)
+            .is_some_and(|(word, mask)| self.words[word] & mask != 0)
+    }
+
+    /// Pixel buffer, row-major, one byte per pixel (0 = off, 1 = on). Unpacked
+    /// from the row bitsets on each call; `pack_into` is what the device gets.
+    #[allow(dead_code)]
+    pub fn pixels(&self) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                pixels.push(u8::from(self.get(x, y)));
+            }
         }
-        self.pixels[uy * self.width + ux] != 0
+        pixels
     }
 
     pub fn invert(&mut self, x: i32, y: i32) {
-        if x < 0 || y < 0 {
-            return;
+        if let Some((word, mask)) = self.bit(x, y) {
+            self.words[word] ^= mask;
         }
-        let ux = x as usize;
-        let uy = y as usize;
-        if ux >= self.width || uy >= self.height {
+    }
+
+    /// Applies `op` to pixels `x0..x1` of row `y`, whole words at a time.
+    /// Callers clip to the canvas first.
+    fn apply_span(&mut self, y: usize, x0: usize, x1: usize, op: SpanOp) {
+        let mut x = x0;
+        while x < x1 {
+            let bit = x % 64;
+            let n = (64 - bit).min(x1 - x);
+            let mask = (u64::MAX >> bit) & !(u64::MAX.checked_shr(bit as u32 + n as u32).unwrap_or(0));
+            let word = &mut self.words[y * self.stride + x / 64];
+            match op {
+                SpanOp::Set => *word |= mask,
+                SpanOp::Clear => *word &= !mask,
+                SpanOp::Toggle => *word ^= mask,
+            }
+            x += n;
+        }
+    }
+
+    fn rect_span(&mut self, x: i32, y: i32, w: i32, h: i32, op: SpanOp) {
+        let x0 = x.max(0) as usize;
+        let y0 = y.max(0) as usize;
+        let x1 = (x.saturating_add(w).max(0) as usize).min(self.width);
+        let y1 = (y.saturating_add(h).max(0) as usize).min(self.height);
+        if x0 >= x1 {
             return;
         }
-        let idx = uy * self.width + ux;
-        self.pixels[idx] ^= 1;
+        for row in y0..y1 {
+            self.apply_span(row, x0, x1, op);
+        }
     }
 
     pub fn rect_fill(&mut self, x: i32, y: i32, w: i32, h: i32, on: bool) {
-        for py in y..(y + h) {
-            for px in x..(x + w) {
-                self.set(px, py, on);
-            }
-        }
+        self.rect_span(x, y, w, h, if on { SpanOp::Set } else { SpanOp::Clear });
     }
 
     pub fn rect_border(&mut self, x: i32, y: i32, w: i32, h: i32, on: bool) {
@@ -73,6 +265,16 @@ impl Canvas {
         }
     }
 
+    /// `thickness` concentric 1px borders growing inwards from the outer rect.
+    pub fn rect_border_thick(&mut self, x: i32, y: i32, w: i32, h: i32, thickness: i32, on: bool) {
+        for i in 0..thickness {
+            if w - 2 * i <= 0 || h - 2 * i <= 0 {
+                break;
+            }
+            self.rect_border(x + i, y + i, w - 2 * i, h - 2 * i, on);
+        }
+    }
+
     pub fn line(&mut self, mut x0: i32, mut y0: i32, x1: i32, y1: i32, on: bool) {
         let dx = (x1 - x0).abs();
         let sx = if x0 < x1 { 1 } else { -1 };
@@ -97,6 +299,20 @@ impl Canvas {
         }
     }
 
+    /// `line` repeated `thickness` times, offset across the line's minor axis
+    /// and centred on it (an even thickness leans right/down).
+    pub fn line_thick(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, thickness: i32, on: bool) {
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        for i in 0..thickness.max(1) {
+            let d = i - (thickness - 1) / 2;
+            if steep {
+                self.line(x0 + d, y0, x1 + d, y1, on);
+            } else {
+                self.line(x0, y0 + d, x1, y1 + d, on);
+            }
+        }
+    }
+
     pub fn line_invert(&mut self, mut x0: i32, mut y0: i32, x1: i32, y1: i32) {
         let dx = (x1 - x0).abs();
         let sx = if x0 < x1 { 1 } else { -1 };
@@ -121,10 +337,199 @@ impl Canvas {
         }
     }
 
+    /// Midpoint circle outline centred on (cx, cy). Off-canvas pixels clip like `set`.
+    pub fn circle(&mut self, cx: i32, cy: i32, r: i32, on: bool) {
+        if r < 0 {
+            return;
+        }
+        let mut x = r;
+        let mut y = 0;
+        let mut err = 1 - r;
+
+        while x >= y {
+            self.set(cx + x, cy + y, on);
+            self.set(cx + y, cy + x, on);
+            self.set(cx - y, cy + x, on);
+            self.set(cx - x, cy + y, on);
+            self.set(cx - x, cy - y, on);
+            self.set(cx - y, cy - x, on);
+            self.set(cx + y, cy - x, on);
+            self.set(cx + x, cy - y, on);
+
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Filled disc using the same midpoint stepping as `circle`, drawn as horizontal spans.
+    #[allow(dead_code)]
+    pub fn circle_fill(&mut self, cx: i32, cy: i32, r: i32, on: bool) {
+        if r < 0 {
+            return;
+        }
+        let mut x = r;
+        let mut y = 0;
+        let mut err = 1 - r;
+
+        while x >= y {
+            for px in (cx - x)..=(cx + x) {
+                self.set(px, cy + y, on);
+                self.set(px, cy - y, on);
+            }
+            for px in (cx - y)..=(cx + y) {
+                self.set(px, cy + x, on);
+                self.set(px, cy - x, on);
+            }
+
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Rectangle outline with quarter-circle corners of radius `r`.
+    /// Falls back to `rect_border` when `r` is 0 or exceeds half the smaller side.
+    #[allow(dead_code)]
+    pub fn rounded_rect_border(&mut self, x: i32, y: i32, w: i32, h: i32, r: i32, on: bool) {
+        if r <= 0 || r > w.min(h) / 2 {
+            self.rect_border(x, y, w, h, on);
+            return;
+        }
+
+        let left = x + r;
+        let right = x + w - 1 - r;
+        let top = y + r;
+        let bottom = y + h - 1 - r;
+
+        for px in left..=right {
+            self.set(px, y, on);
+            self.set(px, y + h - 1, on);
+        }
+        for py in top..=bottom {
+            self.set(x, py, on);
+            self.set(x + w - 1, py, on);
+        }
+
+        let mut cx = r;
+        let mut cy = 0;
+        let mut err = 1 - r;
+        while cx >= cy {
+            for (dx, dy) in [(cx, cy), (cy, cx)] {
+                self.set(right + dx, bottom + dy, on);
+                self.set(left - dx, bottom + dy, on);
+                self.set(right + dx, top - dy, on);
+                self.set(left - dx, top - dy, on);
+            }
+
+            cy += 1;
+            if err < 0 {
+                err += 2 * cy + 1;
+            } else {
+                cx -= 1;
+                err += 2 * (cy - cx) + 1;
+            }
+        }
+    }
+
+    /// Filled rectangle with quarter-circle corners of radius `r`.
+    /// Falls back to `rect_fill` when `r` is 0 or exceeds half the smaller side.
+    #[allow(dead_code)]
+    pub fn rounded_rect_fill(&mut self, x: i32, y: i32, w: i32, h: i32, r: i32, on: bool) {
+        if r <= 0 || r > w.min(h) / 2 {
+            self.rect_fill(x, y, w, h, on);
+            return;
+        }
+
+        let left = x + r;
+        let right = x + w - 1 - r;
+        let top = y + r;
+        let bottom = y + h - 1 - r;
+
+        self.rect_fill(x, top, w, bottom - top + 1, on);
+
+        let mut cx = r;
+        let mut cy = 0;
+        let mut err = 1 - r;
+        while cx >= cy {
+            for (dx, dy) in [(cx, cy), (cy, cx)] {
+                for px in (left - dx)..=(right + dx) {
+                    self.set(px, top - dy, on);
+                    self.set(px, bottom + dy, on);
+                }
+            }
+
+            cy += 1;
+            if err < 0 {
+                err += 2 * cy + 1;
+            } else {
+                cx -= 1;
+                err += 2 * (cy - cx) + 1;
+            }
+        }
+    }
+
     pub fn rect_fill_invert(&mut self, x: i32, y: i32, w: i32, h: i32) {
-        for py in y..(y + h) {
-            for px in x..(x + w) {
-                self.invert(px, py);
+        self.rect_span(x, y, w, h, SpanOp::Toggle);
+    }
+
+    /// Lights the pixels of `pattern` inside the rect; the others are left as they are.
+    pub fn rect_fill_pattern(&mut self, x: i32, y: i32, w: i32, h: i32, pattern: Pattern) {
+        let x0 = x.max(0);
+        let y0 = y.max(0);
+        let x1 = (x + w).min(self.width as i32);
+        let y1 = (y + h).min(self.height as i32);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                if pattern.lit(px, py) {
+                    self.set(px, py, true);
+                }
+            }
+        }
+    }
+
+    /// Copy the lit pixels of `src` onto this canvas with its top-left at (x, y).
+    /// With `invert`, lit source pixels XOR the destination instead of setting it.
+    /// Unlit source pixels leave the destination untouched; clipping follows `set`.
+    pub fn blit(&mut self, src: &Canvas, x: i32, y: i32, invert: bool) {
+        for sy in 0..src.height {
+            for sx in 0..src.width {
+                if !src.get(sx as i32, sy as i32) {
+                    continue;
+                }
+                let dx = x + sx as i32;
+                let dy = y + sy as i32;
+                if invert {
+                    self.invert(dx, dy);
+                } else {
+                    self.set(dx, dy, true);
+                }
+            }
+        }
+    }
+
+    /// Draws the lit cells of `bitmap` (rows top to bottom) with its top-left at
+    /// (`x`, `y`), toggling pixels instead of setting them with `invert`.
+    pub fn draw_bitmap(&mut self, x: i32, y: i32, bitmap: &[Vec<bool>], invert: bool) {
+        for (row, cells) in bitmap.iter().enumerate() {
+            for (col, &on) in cells.iter().enumerate() {
+                if !on {
+                    continue;
+                }
+                let (px, py) = (x + col as i32, y + row as i32);
+                if invert {
+                    self.invert(px, py);
+                } else {
+                    self.set(px, py, true);
+                }
             }
         }
     }
@@ -154,7 +559,6 @@ impl Canvas {
     }
 
     /// Draw text using the built-in 4×5 pixel font at the given scale, inverting pixels.
-    #[allow(dead_code)]
     pub fn draw_text_scaled_invert(&mut self, x: i32, y: i32, text: &str, scale: i32) {
         let s = scale.max(1);
         let advance = 5 * s;
@@ -177,45 +581,111 @@ impl Canvas {
         }
     }
 
-    /// Draw a single character using the built-in 4×5 pixel font at the given scale,
-    /// inverting pixels but clipped to a rectangular region.
+    /// Draw text using the built-in 4×5 pixel font at the given scale, touching
+    /// only pixels inside the clip rectangle. Set pixels, or toggles them with
+    /// `invert`.
     #[allow(clippy::too_many_arguments)]
-    pub fn draw_char_scaled_invert_clipped(
+    pub fn draw_text_scaled_clipped(
         &mut self,
         x: i32,
         y: i32,
-        ch: char,
+        text: &str,
         scale: i32,
         clip_x: i32,
         clip_y: i32,
         clip_w: i32,
         clip_h: i32,
+        invert: bool,
     ) {
         if clip_w <= 0 || clip_h <= 0 {
             return;
         }
 
         let s = scale.max(1);
+        let advance = 5 * s;
         let clip_x2 = clip_x + clip_w - 1;
         let clip_y2 = clip_y + clip_h - 1;
 
-        if let Some(glyph) = tiny_glyph(ch) {
-            for (row, &bits) in glyph.iter().enumerate() {
-                for col in 0..4i32 {
-                    if (bits >> col) & 1 == 1 {
-                        for dy in 0..s {
-                            for dx in 0..s {
-                                let px = x + col * s + dx;
-                                let py = y + row as i32 * s + dy;
-                                if px >= clip_x && px <= clip_x2 && py >= clip_y && py <= clip_y2 {
-                                    self.invert(px, py);
+        let mut cursor_x = x;
+        for ch in text.chars() {
+            if let Some(glyph) = tiny_glyph(ch) {
+                for (row, &bits) in glyph.iter().enumerate() {
+                    for col in 0..4i32 {
+                        if (bits >> col) & 1 == 1 {
+                            for dy in 0..s {
+                                for dx in 0..s {
+                                    let px = cursor_x + col * s + dx;
+                                    let py = y + row as i32 * s + dy;
+                                    if px < clip_x || px > clip_x2 || py < clip_y || py > clip_y2 {
+                                        continue;
+                                    }
+                                    if invert {
+                                        self.invert(px, py);
+                                    } else {
+                                        self.set(px, py, true);
+                                    }
                                 }
                             }
                         }
                     }
                 }
             }
+            cursor_x += advance;
+        }
+    }
+
+    /// Advance width of `text` in the built-in font: `5 * scale` per character,
+    /// including the trailing 1-column gap of the last glyph.
+    pub fn text_width(text: &str, scale: i32) -> i32 {
+        text.chars().count() as i32 * 5 * scale.max(1)
+    }
+
+    /// `draw_text_scaled` positioned inside the columns `[x, x + w)`.
+    pub fn draw_text_scaled_aligned(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: i32,
+        text: &str,
+        scale: i32,
+        align: TextAlign,
+    ) {
+        let text_w = Self::text_width(text, scale);
+        let left = match align {
+            TextAlign::Left => x,
+            TextAlign::Center => x + (w - text_w) / 2,
+            TextAlign::Right => x + w - text_w,
+        };
+        self.draw_text_scaled(left, y, text, scale);
+    }
+
+    /// Draws `text` word-wrapped to `w` columns, one line every `6 * scale` rows,
+    /// stopping before a line would cross `y + h`. Words wider than `w` are
+    /// hard-broken. Returns how many lines were drawn; text past the last one
+    /// is dropped.
+    pub fn draw_text_wrapped(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        text: &str,
+        scale: i32,
+    ) -> usize {
+        let s = scale.max(1);
+        // n glyphs need n * 5s - s columns: the last one has no trailing gap.
+        let cols = ((w + s) / (5 * s)).max(0) as usize;
+        if cols == 0 || h < 5 * s {
+            return 0;
+        }
+        let max_lines = ((h - 5 * s) / (6 * s) + 1) as usize;
+
+        let lines = wrap_words(text, cols);
+        let drawn = lines.len().min(max_lines);
+        for (i, line) in lines.iter().take(drawn).enumerate() {
+            self.draw_text_scaled(x, y + i as i32 * 6 * s, line, s);
         }
+        drawn
     }
 
     /// Convenience: draw at scale 1 (4×5 native size).
@@ -223,40 +693,80 @@ impl Canvas {
         self.draw_text_scaled(x, y, text, 1);
     }
 
-    pub fn to_packed_bytes(&self) -> Vec<u8> {
-        let mut out = vec![0u8; (self.width * self.height).div_ceil(8)];
+    /// Freshly allocated packed frame, for one-off reads such as assertions on
+    /// what a `draw_*` call produced. The render loop uses `pack_into`.
+    #[allow(dead_code)]
+    pub fn packed(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.pack_into(&mut out);
+        out
+    }
 
-        let mut byte_index = 0;
-        let mut bit_index = 0;
-        let mut current = 0u8;
+    /// Packs the canvas into the device's row-major, MSB-first 1bpp format.
+    /// Reuses `out`, so it only allocates the first time.
+    pub fn pack_into(&self, out: &mut Vec<u8>) {
+        out.clear();
+        out.resize((self.width * self.height).div_ceil(8), 0);
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if self.pixels[y * self.width + x] > 0 {
-                    current |= 1 << (7 - bit_index);
+        if self.width.is_multiple_of(8) {
+            // Byte-aligned rows: copy the leading big-endian bytes of each word.
+            let row_bytes = self.width / 8;
+            for (y, dst) in out.chunks_exact_mut(row_bytes).enumerate() {
+                let row = &self.words[y * self.stride..(y + 1) * self.stride];
+                for (i, byte) in dst.iter_mut().enumerate() {
+                    *byte = row[i / 8].to_be_bytes()[i % 8];
                 }
+            }
+            return;
+        }
 
-                bit_index += 1;
-                if bit_index == 8 {
-                    out[byte_index] = current;
-                    byte_index += 1;
-                    bit_index = 0;
-                    current = 0;
+        // Otherwise the bit stream runs on across rows, so go pixel by pixel.
+        let mut bit = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x as i32, y as i32) {
+                    out[bit / 8] |= 0x80 >> (bit % 8);
                 }
+                bit += 1;
             }
         }
+    }
+}
 
-        if bit_index > 0 {
-            out[byte_index] = current;
+/// Greedy word wrap to `cols` characters per line, splitting on whitespace
+/// and hard-breaking words longer than a line.
+fn wrap_words(text: &str, cols: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line: Vec<char> = Vec::new();
+    for word in text.split_whitespace() {
+        let mut word: &[char] = &word.chars().collect::<Vec<_>>();
+        if !line.is_empty() && line.len() + 1 + word.len() <= cols {
+            line.push(' ');
+            line.extend_from_slice(word);
+            continue;
         }
-
-        out
+        if !line.is_empty() {
+            lines.push(line.drain(..).collect());
+        }
+        while word.len() > cols {
+            lines.push(word[..cols].iter().collect());
+            word = &word[cols..];
+        }
+        line.extend_from_slice(word);
+    }
+    if !line.is_empty() {
+        lines.push(line.into_iter().collect());
     }
+    lines
 }
 
 /// 4×5 pixel bitmap font with 1px-thick strokes.
 /// Each entry is 5 rows; in each row, bit N = column N (bit 0 = leftmost).
 fn tiny_glyph(ch: char) -> Option<[u8; 5]> {
+    // Lowercase `b` is kept so bit rates (`Mb`) read differently from bytes (`MB`).
+    if ch == 'b' {
+        return Some([0b0001, 0b0001, 0b0111, 0b1001, 0b0111]);
+    }
     let ch = ch.to_ascii_uppercase();
     Some(match ch {
         '0' => [0b0110, 0b1001, 0b1001, 0b1001, 0b0110],
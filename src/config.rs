@@ -1,8 +1,53 @@
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Result;
-use serde::Deserialize;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Deserializer};
+
+use crate::{icon, schema, toml};
+
+/// Widget `type` values the renderer knows how to draw.
+pub const KNOWN_WIDGET_KINDS: &[&str] = &[
+    "cpu",
+    "volume",
+    "memory",
+    "network",
+    "keyboard",
+    "gauge",
+    "command",
+    "media",
+    "gpu",
+    "topproc",
+    "weather",
+    "sparkline",
+    "analogclock",
+    "cpufreq",
+    "fan",
+    "ping",
+    "cputemp",
+    "backlight",
+    "devicebattery",
+    "barchart",
+];
+
+/// Name `DashboardConfig::cycle_profile` gives the top-level `widgets`.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// `Widget::weather_ms` when unset: one fetch an hour.
+pub const DEFAULT_WEATHER_MS: u32 = 3_600_000;
+// Fetching more often than this only annoys the weather services.
+const MIN_WEATHER_MS: u32 = 60_000;
+
+/// `BarConfig::direction` values, named after the axis and the fill's travel.
+pub const BAR_DIRECTIONS: &[&str] = &[
+    "horizontal",
+    "horizontal-reverse",
+    "vertical",
+    "vertical-down",
+];
 
 #[derive(Debug, Deserialize)]
 pub struct DashboardConfig {
@@ -10,9 +55,54 @@ pub struct DashboardConfig {
     pub config_name: String,
     #[serde(default = "default_refresh_rate")]
     pub refresh_rate_ms: u32,
+    /// Slowest frame interval to back off to while frames stay identical.
+    /// Unset keeps the loop at `refresh_rate_ms`.
+    #[serde(default)]
+    pub idle_refresh_ms: Option<u32>,
+    /// Blank the panel after this long without a changed frame, waking on the
+    /// next change. Unset never blanks.
+    #[serde(default)]
+    pub idle_blank_ms: Option<u32>,
+    /// What the panel shows once `idle_blank_ms` has passed.
+    #[serde(default)]
+    pub idle_mode: IdleMode,
+    /// Monochrome icon file for `idle_mode: "bounce"`; a small star when unset.
+    #[serde(default)]
+    pub idle_sprite: Option<PathBuf>,
+    #[serde(skip)]
+    pub idle_sprite_bitmap: Option<Vec<Vec<bool>>>,
+    /// Nudge the whole frame by up to 1px every this many ms against burn-in.
+    /// Unset keeps exact positioning.
+    #[serde(default)]
+    pub burn_in_shift_ms: Option<u32>,
     pub display: Display,
     #[serde(default)]
+    pub device: DeviceConfig,
+    #[serde(default)]
+    pub boot: BootConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub keyboard_leds: KeyboardLedConfig,
+    /// Animation between the old and new layout on a reload. Unset cuts over.
+    #[serde(default)]
+    pub transition: Option<TransitionConfig>,
+    /// Publish the sampled metrics to an MQTT broker (needs the `mqtt` feature).
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    #[serde(default)]
     pub widgets: Vec<Widget>,
+    /// Named alternatives to `widgets`, cycled through with SIGUSR1 (next)
+    /// and SIGUSR2 (previous) in name order after `default`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Vec<Widget>>,
+    /// Profile currently in `widgets`; `None` is `default`. While another one
+    /// is active, the default set waits in `profiles` under `DEFAULT_PROFILE`.
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    /// Keys in the file no setting reads (typos, usually), found by `load`.
+    #[serde(skip)]
+    pub unknown_fields: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,6 +111,217 @@ pub struct Display {
     pub height: usize,
     #[serde(default)]
     pub background: u8,
+    /// Panel brightness sent once at startup. Experimental: only applied when
+    /// `device.brightness_report` is set.
+    #[serde(default)]
+    pub brightness: Option<u8>,
+    /// `0` or `180` degrees, for a keyboard mounted upside down.
+    #[serde(default)]
+    pub rotation: u32,
+    #[serde(default)]
+    pub mirror: Mirror,
+}
+
+/// Mirroring applied to the finished frame, after `rotation`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Left and right swapped.
+    Horizontal,
+    /// Top and bottom swapped.
+    Vertical,
+}
+
+/// Optional USB identity and report layout overrides; unset fields fall back to
+/// the Apex5 defaults. IDs accept either JSON numbers or hex strings like
+/// `"0x1038"` / `"161C"`.
+#[derive(Debug, Default, Deserialize)]
+pub struct DeviceConfig {
+    #[serde(default, deserialize_with = "deserialize_hex")]
+    pub vendor_id: Option<u16>,
+    #[serde(default, deserialize_with = "deserialize_hex")]
+    pub product_id: Option<u16>,
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Explicit hidraw node (e.g. `/dev/hidraw3`); skips sysfs discovery.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Discovery only picks nodes whose sysfs device path contains this (e.g.
+    /// the USB port `1-3`), to tell apart keyboards with the same VID/PID.
+    #[serde(default)]
+    pub sysfs_path: Option<String>,
+    /// First byte of every output report.
+    #[serde(default, deserialize_with = "deserialize_hex")]
+    pub report_id: Option<u8>,
+    /// Total report length: report id + packed frame + 1 padding byte. Only
+    /// checked against the display size; any other value is rejected.
+    #[serde(default)]
+    pub packet_len: Option<usize>,
+    /// Experimental: read the input reports the device sends (e.g. media
+    /// keys). Each one is logged at debug level and triggers a fast refresh.
+    #[serde(default)]
+    pub read_input: bool,
+    /// Experimental: report id for `display.brightness`. There is no known
+    /// default, so brightness is never sent without it.
+    #[serde(default, deserialize_with = "deserialize_hex")]
+    pub brightness_report: Option<u8>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleMode {
+    /// Dark panel.
+    #[default]
+    Blank,
+    /// A sprite drifting around and bouncing off the edges.
+    Bounce,
+}
+
+/// Startup animation shown before the widgets.
+#[derive(Debug, Deserialize)]
+pub struct BootConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_boot_duration")]
+    pub duration_ms: u32,
+    #[serde(default)]
+    pub style: BootStyle,
+}
+
+impl Default for BootConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration_ms: default_boot_duration(),
+            style: BootStyle::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BootStyle {
+    /// Spinning gear and star logo.
+    #[default]
+    Gear,
+    /// Dark screen for `duration_ms`, e.g. to hide USB settling glitches.
+    Blank,
+    /// No boot phase at all, same as `enabled: false`.
+    None,
+}
+
+/// How the panel changes over when the layout does.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct TransitionConfig {
+    #[serde(default)]
+    pub style: TransitionStyle,
+    #[serde(default = "default_transition_duration")]
+    pub duration_ms: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransitionStyle {
+    /// Pixels switch over in the boot logo's noise order.
+    #[default]
+    Dissolve,
+    /// The new layout sweeps in from the left.
+    Wipe,
+}
+
+impl BootConfig {
+    /// How long the boot phase lasts; zero when it's disabled.
+    pub fn duration(&self) -> Duration {
+        if !self.enabled || self.style == BootStyle::None {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(self.duration_ms as u64)
+    }
+}
+
+/// Exact LED directories (e.g. `input5::capslock`, relative to
+/// `/sys/class/leds`, or absolute) for keyboards whose names aren't detected.
+#[derive(Debug, Default, Deserialize)]
+pub struct KeyboardLedConfig {
+    #[serde(default)]
+    pub capslock: Option<PathBuf>,
+    #[serde(default)]
+    pub numlock: Option<PathBuf>,
+    #[serde(default)]
+    pub scrolllock: Option<PathBuf>,
+    /// Backlight LED for the `backlight` widget; found by name when unset.
+    #[serde(default)]
+    pub backlight: Option<PathBuf>,
+}
+
+/// Where and how often the metrics are published over MQTT.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MqttConfig {
+    /// `mqtt://host[:port]` or `host[:port]`; the port defaults to 1883.
+    pub broker: String,
+    /// Topics are `<topic_prefix>/cpu`, `/memory`, `/volume`, `/network`, `/leds`.
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub topic_prefix: String,
+    /// Defaults to `stained-steel-<pid>`.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Shortest time between two publishes; 0 publishes every frame.
+    #[serde(default = "default_mqtt_interval")]
+    pub interval_ms: u32,
+    /// Ask the broker to keep the last message of each topic for new subscribers.
+    #[serde(default)]
+    pub retain: bool,
+}
+
+impl MqttConfig {
+    /// `broker` as a `host:port` to connect to.
+    pub fn broker_addr(&self) -> Option<String> {
+        let rest = self.broker.strip_prefix("mqtt://").unwrap_or(&self.broker);
+        let host = rest.trim_end_matches('/');
+        if host.is_empty() || host.contains('/') {
+            return None;
+        }
+        // IPv6 addresses need brackets: `[::1]:1883`.
+        let port = match host.strip_prefix('[') {
+            Some(v6) => v6.split_once("]:").map(|(_, port)| port),
+            None => host.split_once(':').map(|(_, port)| port),
+        };
+        match port {
+            Some(port) => port.parse::<u16>().ok().map(|_| host.to_string()),
+            None => Some(format!("{host}:1883")),
+        }
+    }
+}
+
+/// Tuning for the volume widget's live output meter.
+#[derive(Debug, Deserialize)]
+pub struct AudioConfig {
+    /// Level (0-100) treated as silence; raise it if the meter flickers at idle.
+    #[serde(default = "default_audio_noise_floor", alias = "audio_noise_floor")]
+    pub noise_floor: f32,
+    /// Gain applied to the captured signal; above 1 fills the meter sooner.
+    #[serde(default = "default_audio_sensitivity", alias = "audio_sensitivity")]
+    pub sensitivity: f32,
+    /// How much of the previous level carries over each sample (0-1);
+    /// higher is steadier but slower to react.
+    #[serde(default = "default_audio_smoothing", alias = "audio_smoothing")]
+    pub smoothing: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            noise_floor: default_audio_noise_floor(),
+            sensitivity: default_audio_sensitivity(),
+            smoothing: default_audio_smoothing(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -31,18 +332,115 @@ pub struct Widget {
     pub enabled: bool,
     #[serde(default)]
     pub refresh_rate_ms: Option<u32>,
+    /// Redraw this widget at most this often; other frames reuse its last pixels.
+    /// Unset redraws every frame. Independent of `refresh_rate_ms` (metric sampling).
+    #[serde(default)]
+    pub render_interval_ms: Option<u32>,
     pub position: Position,
+    /// Network interface a `network` widget shows; unset uses the default one.
     #[serde(default)]
     pub interface: Option<String>,
     #[serde(default)]
     pub show_icon: bool,
+    /// `analogclock`: draw a second hand too (the widget then changes every second).
+    #[serde(default)]
+    pub show_seconds: bool,
+    /// Metric a generic widget (`gauge`, `sparkline`) reads: `cpu`, `memory`, `gpu` or `volume`.
+    /// A `barchart` reads a list instead: `cpucores` (default) or `coretemps`.
+    #[serde(default)]
+    pub source: Option<String>,
     #[serde(default)]
     pub bar: Option<BarConfig>,
     #[serde(default)]
     pub graph: Option<GraphConfig>,
+    /// `network` widgets: `text` (default) or `graph`. `memory` widgets:
+    /// `percent` (default) or `absolute` for `12.3/32G` text; `graph` and `bar`
+    /// are accepted as `percent`, since bar vs graph follows the sections. `cpufreq`
+    /// widgets: `ghz` (default, `3.4G`) or `mhz` (`3400`). `cputemp` widgets:
+    /// `text` (default, package °C) or `cores` (a bar per core). `backlight`
+    /// widgets: `bar` (default) or `sun` (an icon filling up). Other types pick
+    /// bar or graph from which section is present and ignore this.
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Network rate units: `bytes` (default, `K`/`M`/...) or `bits` (`Kb`/`Mb`/...).
+    #[serde(default)]
+    pub units: Option<String>,
+    /// Fixed `"lat,lon"` for a `weather` widget; unset locates by IP.
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Custom weather endpoint (Open-Meteo or wttr.in `?format=j1` JSON).
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Time between weather fetches, for both the IP-located lookup and a
+    /// fixed `location`/`url` (default one hour, at least one minute).
+    #[serde(default)]
+    pub weather_ms: Option<u32>,
+    /// Host name or address a `ping` widget pings.
+    #[serde(default)]
+    pub host: Option<String>,
+    /// UPower device a `devicebattery` widget shows: part of its model name
+    /// (e.g. `MX Master`), native path or object path.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Program a `command` widget runs; its trimmed stdout is displayed.
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Monochrome icon file (PBM, PGM or JSON rows), relative to the config
+    /// file. Replaces the built-in cpu/volume icon, or sits in the top-left.
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+    /// `icon` decoded by `DashboardConfig::load`.
+    #[serde(skip)]
+    pub icon_bitmap: Option<Vec<Vec<bool>>>,
+    /// Metric value (in the widget's `source` units) above which it alerts.
+    #[serde(default)]
+    pub alert_threshold: Option<f32>,
+    /// Invert the widget's whole rect while it alerts; same as `alert_mode: "invert"`.
+    #[serde(default)]
+    pub alert_invert: bool,
+    /// How an alert shows: `invert` (steady) or `blink`.
+    #[serde(default)]
+    pub alert_mode: Option<AlertMode>,
+    /// Frames between blink toggles in `blink` mode.
+    #[serde(default = "default_alert_blink_frames")]
+    pub alert_blink_frames: u32,
+    /// `command`/`media` text wraps onto more lines instead of scrolling.
+    #[serde(default)]
+    pub wrap: bool,
+    /// Value range a `gauge` arc, `sparkline` height or `cputemp`/`barchart`
+    /// bar spans (default 0-100).
+    #[serde(default)]
+    pub min: Option<f32>,
+    #[serde(default)]
+    pub max: Option<f32>,
+    /// `barchart`: draw at most this many bars (the first ones).
+    #[serde(default)]
+    pub max_bars: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertMode {
+    Invert,
+    Blink,
+}
+
+impl Widget {
+    /// `alert_mode`, falling back to `Invert` when only `alert_invert` is set.
+    pub fn alert_mode(&self) -> Option<AlertMode> {
+        self.alert_mode
+            .or(self.alert_invert.then_some(AlertMode::Invert))
+    }
+
+    /// `min`/`max` with the 0-100 defaults filled in.
+    pub fn range(&self) -> (f32, f32) {
+        (self.min.unwrap_or(0.0), self.max.unwrap_or(100.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -50,32 +448,414 @@ pub struct Position {
     pub h: i32,
 }
 
+impl Position {
+    pub fn intersects(&self, other: &Position) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct BarConfig {
+    /// `horizontal` (left→right), `horizontal-reverse` (right→left),
+    /// `vertical` (bottom→top) or `vertical-down` (top→bottom).
     #[serde(default = "default_direction")]
     pub direction: String,
     #[serde(default)]
     pub border: bool,
+    /// Border width in pixels when `border` is set.
+    #[serde(default = "default_border_thickness")]
+    pub border_thickness: i32,
+    /// Draw the percentage centred over the bar.
+    #[serde(default)]
+    pub show_value: bool,
+    /// `solid` (default) or `segments` for an LED-meter look.
+    #[serde(default = "default_bar_style")]
+    pub style: String,
+    /// Block count for the `segments` style.
+    #[serde(default = "default_bar_segments")]
+    pub segments: usize,
+    /// Values mapped to an empty and a full bar; outside values are clamped.
+    #[serde(default)]
+    pub min: f32,
+    #[serde(default = "default_range_max")]
+    pub max: f32,
+}
+
+impl BarConfig {
+    /// Border width to draw, 0 without a border.
+    pub fn border_px(&self) -> i32 {
+        if self.border {
+            self.border_thickness
+        } else {
+            0
+        }
+    }
+
+    /// Blocks to split the bar into; 0 means a solid fill.
+    pub fn segment_count(&self) -> usize {
+        if self.style == "segments" {
+            self.segments
+        } else {
+            0
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GraphConfig {
     #[serde(default)]
     pub history: usize,
+    /// Scale to the highest value in the history (plus headroom) instead of 0-100.
+    #[serde(default)]
+    pub autoscale: bool,
+    /// Dotted guide lines at these percentages of the graph's height.
+    #[serde(default)]
+    pub gridlines: Vec<f32>,
 }
 
 impl DashboardConfig {
+    /// Reads a `.toml` file as TOML and anything else as JSON. Unknown keys
+    /// don't fail the load; they're listed in `unknown_fields`.
     pub fn load(path: &Path) -> Result<Self> {
         let raw = fs::read_to_string(path)?;
-        let cfg: DashboardConfig = serde_json::from_str(&raw)?;
+        let is_toml = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let (mut cfg, value): (DashboardConfig, serde_json::Value) = if is_toml {
+            let value = toml::parse(&raw)?;
+            (serde_json::from_value(value.clone())?, value)
+        } else {
+            // Typed first, so syntax and type errors keep their line numbers.
+            (serde_json::from_str(&raw)?, serde_json::from_str(&raw)?)
+        };
+        cfg.unknown_fields = schema::unknown_fields(&value);
+        let base = path.parent().unwrap_or(Path::new("."));
+        if let Some(sprite) = &cfg.idle_sprite {
+            cfg.idle_sprite_bitmap = Some(icon::load_icon(&base.join(sprite))?);
+        }
+        for widget in cfg
+            .widgets
+            .iter_mut()
+            .chain(cfg.profiles.values_mut().flatten())
+        {
+            if let Some(icon) = &widget.icon {
+                widget.icon_bitmap = Some(icon::load_icon(&base.join(icon))?);
+            }
+        }
         Ok(cfg)
     }
 
-    pub fn preferred_network_interface(&self) -> Option<String> {
-        self.widgets
+    /// Applies deployment overrides from the environment on top of the file:
+    /// `STAINED_REFRESH_MS`, `STAINED_DEVICE_PATH` and `STAINED_NETWORK_IFACE`
+    /// (the interface of every `network` widget). Unset or empty variables
+    /// leave the config as it is.
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        if let Some(raw) = env_override("STAINED_REFRESH_MS")? {
+            self.refresh_rate_ms = match raw.trim().parse::<u32>() {
+                Ok(ms) if ms > 0 => ms,
+                _ => bail!("STAINED_REFRESH_MS: \"{raw}\" must be a positive number of ms"),
+            };
+        }
+        if let Some(raw) = env_override("STAINED_DEVICE_PATH")? {
+            self.device.path = Some(PathBuf::from(raw));
+        }
+        if let Some(raw) = env_override("STAINED_NETWORK_IFACE")? {
+            let iface = raw.trim();
+            if iface.contains(char::is_whitespace) || iface.contains('/') {
+                bail!("STAINED_NETWORK_IFACE: \"{raw}\" is not an interface name");
+            }
+            let widgets = self
+                .widgets
+                .iter_mut()
+                .chain(self.profiles.values_mut().flatten());
+            for widget in widgets.filter(|w| w.kind == "network") {
+                widget.interface = Some(iface.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every enabled widget has a known type and a non-empty position
+    /// inside the display, reporting all problems at once.
+    pub fn validate(&self) -> Result<()> {
+        let width = self.display.width as i32;
+        let height = self.display.height as i32;
+        let mut problems = Vec::new();
+
+        self.validate_widgets(&self.widgets, "", &mut problems);
+        for (profile, widgets) in &self.profiles {
+            if self.active_profile.is_none() && profile == DEFAULT_PROFILE {
+                problems.push(format!(
+                    "profiles: \"{DEFAULT_PROFILE}\" is the top-level widgets, pick another name"
+                ));
+            }
+            self.validate_widgets(widgets, &format!("profile \"{profile}\" "), &mut problems);
+        }
+
+        if let Some(bitmap) = &self.idle_sprite_bitmap
+            && (bitmap[0].len() > self.display.width || bitmap.len() > self.display.height)
+        {
+            problems.push(format!(
+                "idle_sprite: {}x{} is larger than the {width}x{height} display",
+                bitmap[0].len(),
+                bitmap.len()
+            ));
+        }
+        if self.display.rotation != 0 && self.display.rotation != 180 {
+            problems.push(format!(
+                "display: rotation {} must be 0 or 180",
+                self.display.rotation
+            ));
+        }
+
+        if !(0.0..=100.0).contains(&self.audio.noise_floor) {
+            problems.push(format!(
+                "audio: noise_floor {} must be within 0-100",
+                self.audio.noise_floor
+            ));
+        }
+        if !(self.audio.sensitivity.is_finite() && self.audio.sensitivity > 0.0) {
+            problems.push(format!(
+                "audio: sensitivity {} must be positive",
+                self.audio.sensitivity
+            ));
+        }
+        if !(0.0..1.0).contains(&self.audio.smoothing) {
+            problems.push(format!(
+                "audio: smoothing {} must be at least 0 and below 1",
+                self.audio.smoothing
+            ));
+        }
+
+        if let Some(mqtt) = &self.mqtt {
+            if mqtt.broker_addr().is_none() {
+                problems.push(format!(
+                    "mqtt: broker \"{}\" must be mqtt://host[:port]",
+                    mqtt.broker
+                ));
+            }
+            if mqtt.topic_prefix.is_empty()
+                || mqtt.topic_prefix.contains(['#', '+'])
+                || mqtt.topic_prefix.ends_with('/')
+            {
+                problems.push(format!(
+                    "mqtt: topic_prefix \"{}\" must be a topic without wildcards or a trailing /",
+                    mqtt.topic_prefix
+                ));
+            }
+        }
+
+        if !problems.is_empty() {
+            bail!("invalid config:\n  {}", problems.join("\n  "));
+        }
+        Ok(())
+    }
+
+    /// Problems with the enabled `widgets`, each named with `label` first.
+    fn validate_widgets(&self, widgets: &[Widget], label: &str, problems: &mut Vec<String>) {
+        let width = self.display.width as i32;
+        let height = self.display.height as i32;
+        for (index, widget) in widgets.iter().enumerate() {
+            if !widget.enabled {
+                continue;
+            }
+            let name = format!("{label}widget #{index} ({})", widget.kind);
+            let p = &widget.position;
+
+            if !KNOWN_WIDGET_KINDS.contains(&widget.kind.as_str()) {
+                problems.push(format!(
+                    "{name}: unknown type, expected one of {}",
+                    KNOWN_WIDGET_KINDS.join(", ")
+                ));
+            }
+            if widget.kind == "command" && widget.command.as_deref().is_none_or(str::is_empty) {
+                problems.push(format!("{name}: missing \"command\""));
+            }
+            if widget.kind == "barchart"
+                && let Some(source) = &widget.source
+                && source != "cpucores"
+                && source != "coretemps"
+            {
+                problems.push(format!(
+                    "{name}: source \"{source}\" must be \"cpucores\" or \"coretemps\""
+                ));
+            }
+            if widget.kind == "devicebattery" && widget.device.as_deref().is_none_or(str::is_empty)
+            {
+                problems.push(format!("{name}: missing \"device\""));
+            }
+            if widget.kind == "ping" {
+                match widget.host.as_deref() {
+                    None | Some("") => problems.push(format!("{name}: missing \"host\"")),
+                    // Would otherwise be taken as a ping option.
+                    Some(host) if host.starts_with('-') => {
+                        problems.push(format!("{name}: invalid host \"{host}\""))
+                    }
+                    Some(_) => {}
+                }
+            }
+            let modes: &[&str] = match widget.kind.as_str() {
+                "network" => &["text", "graph"],
+                "memory" => &["percent", "absolute", "graph", "bar"],
+                "cpufreq" => &["ghz", "mhz"],
+                "cputemp" => &["text", "cores"],
+                "backlight" => &["bar", "sun"],
+                _ => &[],
+            };
+            if let Some(mode) = &widget.mode
+                && !modes.is_empty()
+                && !modes.contains(&mode.as_str())
+            {
+                problems.push(format!(
+                    "{name}: mode \"{mode}\" must be \"{}\"",
+                    modes.join("\" or \"")
+                ));
+            }
+            if let Some(units) = &widget.units
+                && units != "bytes"
+                && units != "bits"
+            {
+                problems.push(format!(
+                    "{name}: units \"{units}\" must be \"bytes\" or \"bits\""
+                ));
+            }
+            let (min, max) = widget.range();
+            if min >= max {
+                problems.push(format!("{name}: min {min} must be below max {max}"));
+            }
+            if let Some(bar) = &widget.bar {
+                if bar.min >= bar.max {
+                    problems.push(format!(
+                        "{name}: bar min {} must be below max {}",
+                        bar.min, bar.max
+                    ));
+                }
+                if bar.border_thickness < 1 {
+                    problems.push(format!(
+                        "{name}: bar border_thickness {} must be at least 1",
+                        bar.border_thickness
+                    ));
+                }
+                if !BAR_DIRECTIONS.contains(&bar.direction.as_str()) {
+                    problems.push(format!(
+                        "{name}: bar direction \"{}\" must be one of {}",
+                        bar.direction,
+                        BAR_DIRECTIONS.join(", ")
+                    ));
+                }
+                if bar.style != "solid" && bar.style != "segments" {
+                    problems.push(format!(
+                        "{name}: bar style \"{}\" must be \"solid\" or \"segments\"",
+                        bar.style
+                    ));
+                } else if bar.style == "segments" && bar.segments == 0 {
+                    problems.push(format!("{name}: bar segments must be at least 1"));
+                }
+            }
+            if let Some(graph) = &widget.graph
+                && let Some(level) = graph.gridlines.iter().find(|l| !(0.0..=100.0).contains(*l))
+            {
+                problems.push(format!(
+                    "{name}: graph gridline {level} must be within 0-100"
+                ));
+            }
+            if let Some(ms) = widget.weather_ms
+                && ms < MIN_WEATHER_MS
+            {
+                problems.push(format!(
+                    "{name}: weather_ms {ms} must be at least {MIN_WEATHER_MS} (one minute)"
+                ));
+            }
+            if let Some(location) = &widget.location
+                && parse_location(location).is_none()
+            {
+                problems.push(format!(
+                    "{name}: location \"{location}\" must be \"lat,lon\""
+                ));
+            }
+            if let Some(bitmap) = &widget.icon_bitmap {
+                let (icon_w, icon_h) = (bitmap[0].len() as i32, bitmap.len() as i32);
+                if icon_w > p.w || icon_h > p.h {
+                    problems.push(format!(
+                        "{name}: icon {icon_w}x{icon_h} is larger than the widget's {}x{}",
+                        p.w, p.h
+                    ));
+                }
+            }
+            if p.w <= 0 || p.h <= 0 {
+                problems.push(format!("{name}: size {}x{} must be positive", p.w, p.h));
+                continue;
+            }
+            if p.x < 0 || p.y < 0 || p.x + p.w > width || p.y + p.h > height {
+                problems.push(format!(
+                    "{name}: rect x={} y={} w={} h={} exceeds the {width}x{height} display",
+                    p.x, p.y, p.w, p.h
+                ));
+            }
+        }
+    }
+
+    /// Overlapping widgets in `widgets` and in every entry of `profiles`, one
+    /// line per pair. Some overlap is intentional (the keyboard row sits over
+    /// the volume bar), so callers should only warn.
+    pub fn overlap_warnings(&self) -> Vec<String> {
+        let label = if self.profiles.is_empty() {
+            String::new()
+        } else {
+            format!("profile \"{}\" ", self.profile_name())
+        };
+        let mut warnings = widget_overlaps(&label, &self.widgets);
+        for (profile, widgets) in &self.profiles {
+            warnings.extend(widget_overlaps(&format!("profile \"{profile}\" "), widgets));
+        }
+        warnings
+    }
+
+    /// Name of the widget set in `widgets`.
+    pub fn profile_name(&self) -> &str {
+        self.active_profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// Moves `step` profiles forward (or back, if negative) from the active
+    /// one, wrapping around. Returns false when there are no profiles.
+    pub fn cycle_profile(&mut self, step: isize) -> bool {
+        if self.profiles.is_empty() {
+            return false;
+        }
+        let mut names: Vec<&str> = self
+            .profiles
+            .keys()
+            .map(String::as_str)
+            .filter(|&name| name != DEFAULT_PROFILE)
+            .chain(self.active_profile.as_deref())
+            .collect();
+        names.sort_unstable();
+        names.insert(0, DEFAULT_PROFILE);
+        let current = names
             .iter()
-            .find(|w| w.enabled && w.kind == "network")
-            .and_then(|w| w.interface.clone())
+            .position(|&name| name == self.profile_name())
+            .unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(names.len() as isize) as usize;
+        let next = names[next].to_string();
+        self.select_profile(&next)
+    }
+
+    /// Swaps the named profile into `widgets`. False if there is no such profile.
+    pub fn select_profile(&mut self, name: &str) -> bool {
+        if name == self.profile_name() {
+            return true;
+        }
+        let Some(widgets) = self.profiles.remove(name) else {
+            return false;
+        };
+        let previous = std::mem::replace(&mut self.widgets, widgets);
+        self.profiles
+            .insert(self.profile_name().to_string(), previous);
+        self.active_profile = (name != DEFAULT_PROFILE).then(|| name.to_string());
+        true
     }
 
     pub fn widget_refresh_rate_ms(&self, kind: &str) -> Option<u32> {
@@ -87,10 +867,145 @@ impl DashboardConfig {
     }
 }
 
+/// Polls a config file's mtime (at most once a second) to detect edits.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified_time(path),
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < Duration::from_secs(1) {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            return true;
+        }
+        false
+    }
+}
+
+/// A set, non-empty environment variable; non-UTF-8 values are an error.
+fn env_override(name: &str) -> Result<Option<String>> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => bail!("{name}: value is not valid UTF-8"),
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn deserialize_hex<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u64>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum HexValue {
+        Number(u64),
+        Text(String),
+    }
+
+    let value = match Option::<HexValue>::deserialize(deserializer)? {
+        None => return Ok(None),
+        Some(HexValue::Number(value)) => value,
+        Some(HexValue::Text(text)) => {
+            let trimmed = text.trim();
+            let digits = trimmed
+                .strip_prefix("0x")
+                .or_else(|| trimmed.strip_prefix("0X"))
+                .unwrap_or(trimmed);
+            u64::from_str_radix(digits, 16)
+                .map_err(|_| serde::de::Error::custom(format!("invalid hex value {text:?}")))?
+        }
+    };
+
+    T::try_from(value)
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("value {value:#x} out of range")))
+}
+
+/// Enabled widgets whose rectangles share at least one pixel, as warnings
+/// prefixed with `label` (empty, or `profile "name" `).
+pub fn widget_overlaps(label: &str, widgets: &[Widget]) -> Vec<String> {
+    let enabled: Vec<(usize, &Widget)> = widgets
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| w.enabled)
+        .collect();
+
+    let mut warnings = Vec::new();
+    for (i, (a_index, a)) in enabled.iter().enumerate() {
+        for (b_index, b) in &enabled[i + 1..] {
+            if a.position.intersects(&b.position) {
+                warnings.push(format!(
+                    "{label}widget #{a_index} ({}) overlaps widget #{b_index} ({})",
+                    a.kind, b.kind
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// Parses `"lat,lon"` in decimal degrees.
+pub fn parse_location(value: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = value.split_once(',')?;
+    let lat = lat.trim().parse::<f64>().ok()?;
+    let lon = lon.trim().parse::<f64>().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
 fn default_refresh_rate() -> u32 {
     33
 }
 
+fn default_boot_duration() -> u32 {
+    2100
+}
+
+fn default_audio_noise_floor() -> f32 {
+    1.4
+}
+
+fn default_audio_sensitivity() -> f32 {
+    1.0
+}
+
+fn default_audio_smoothing() -> f32 {
+    0.80
+}
+
+fn default_transition_duration() -> u32 {
+    400
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "stained_steel".to_string()
+}
+
+fn default_mqtt_interval() -> u32 {
+    1000
+}
+
 fn default_enabled() -> bool {
     true
 }
@@ -98,3 +1013,23 @@ fn default_enabled() -> bool {
 fn default_direction() -> String {
     "horizontal".to_string()
 }
+
+fn default_alert_blink_frames() -> u32 {
+    15
+}
+
+fn default_range_max() -> f32 {
+    100.0
+}
+
+fn default_bar_style() -> String {
+    "solid".to_string()
+}
+
+fn default_border_thickness() -> i32 {
+    1
+}
+
+fn default_bar_segments() -> usize {
+    10
+}
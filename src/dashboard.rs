@@ -1,8 +1,11 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::f32::consts::TAU;
 use std::time::{Duration, Instant};
-use crate::canvas::Canvas;
-use crate::config::{DashboardConfig, Position, Widget};
+use crate::canvas::{Canvas, Pattern, TextAlign};
+use crate::config::{
+    self, AlertMode, BarConfig, BootConfig, BootStyle, DashboardConfig, Display, Mirror, Position,
+    TransitionStyle, Widget,
+};
 use crate::metrics::MetricsSample;
 use crate::weather::{WeatherCache, WeatherCondition};
 
@@ -18,13 +21,33 @@ enum TransitionType {
     Blinds,
 }
 
+/// Which of a widget's series a history holds; only network graphs keep two.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Series {
+    Main,
+    Upload,
+}
+
+/// Cached pixels of a throttled widget and when they were drawn.
+struct WidgetLayer {
+    canvas: Canvas,
+    drawn_at: Instant,
+}
+
 pub struct DashboardRenderer {
     canvas: Canvas,
+    packed: Vec<u8>,
     width: usize,
     height: usize,
     boot_started: Instant,
+    // Frames rendered so far; drives frame-based effects like alert blinking.
+    frame_count: u64,
     boot_duration: Duration,
-    mem_history: VecDeque<f32>,
+    boot_style: BootStyle,
+    // Graph series keyed by widget index in `config.widgets`.
+    histories: HashMap<(usize, Series), VecDeque<f32>>,
+    // Off-screen layers of widgets with `render_interval_ms`, keyed like `histories`.
+    layers: HashMap<usize, WidgetLayer>,
     volume_display: Option<i32>,
     volume_target: i32,
     vol_step_from: i32,
@@ -48,6 +71,8 @@ pub struct DashboardRenderer {
     scroll_anim_from: bool,
     scroll_anim_to: bool,
     silence_start: Option<Instant>,
+    // The sine gap is drawn and reacting to sound; false without that band.
+    audio_active: bool,
     idle_sine_phase: f32,
     sep_sine_phase: f32,
     // Clock / volume overlay state
@@ -62,18 +87,29 @@ pub struct DashboardRenderer {
     melt_seed: u32, // Random seed for DOOM melt pattern
     // Weather
     weather: WeatherCache,
+    // Weather widgets with their own location/url; others share `weather`.
+    weather_widgets: HashMap<usize, WeatherCache>,
     weather_anim_phase: f32,
+    // Blade angle of each fan widget, keyed by widget index.
+    fan_phases: HashMap<usize, f32>,
+    // Added to widget indices when looking up per-widget sample data, which is
+    // keyed across every dashboard sharing the collector.
+    widget_offset: usize,
 }
 
 impl DashboardRenderer {
-    pub fn new(width: usize, height: usize) -> Self {
+    pub fn new(width: usize, height: usize, boot: &BootConfig) -> Self {
         Self {
             canvas: Canvas::new(width, height),
+            packed: Vec::with_capacity((width * height).div_ceil(8)),
             width,
             height,
             boot_started: Instant::now(),
-            boot_duration: Duration::from_millis(2100),
-            mem_history: VecDeque::new(),
+            frame_count: 0,
+            boot_duration: boot.duration(),
+            boot_style: boot.style,
+            histories: HashMap::new(),
+            layers: HashMap::new(),
             volume_display: None,
             volume_target: 0,
             vol_step_from: 0,
@@ -97,6 +133,7 @@ impl DashboardRenderer {
             scroll_anim_from: false,
             scroll_anim_to: false,
             silence_start: None,
+            audio_active: false,
             idle_sine_phase: 0.0,
             sep_sine_phase: 0.0,
             show_volume_overlay: false,
@@ -112,8 +149,76 @@ impl DashboardRenderer {
                 tv.tv_nsec as u32
             },
             weather: WeatherCache::new(),
+            weather_widgets: HashMap::new(),
             weather_anim_phase: 0.0,
+            fan_phases: HashMap::new(),
+            widget_offset: 0,
+        }
+    }
+
+    /// Drop the boot logo so the next `render` goes straight to the widgets.
+    pub fn skip_boot(&mut self) {
+        self.boot_duration = Duration::ZERO;
+    }
+
+    /// Key of this config's first widget in the sample's per-widget maps
+    /// (`command_output`, `ping_rtts`, `device_batteries`).
+    pub fn set_widget_offset(&mut self, offset: usize) {
+        self.widget_offset = offset;
+    }
+
+    /// Called after `old` was swapped for `new` in the config (a profile
+    /// switch). Graph history and fan angles survive where the widget at the
+    /// same index has the same type, weather caches where it also has the same
+    /// source. Throttled layers are always redrawn.
+    pub fn switch_widgets(&mut self, old: &[Widget], new: &[Widget]) {
+        let same_kind = |index: usize| match (old.get(index), new.get(index)) {
+            (Some(a), Some(b)) => a.kind == b.kind,
+            _ => false,
+        };
+        self.histories.retain(|&(index, _), _| same_kind(index));
+        self.fan_phases.retain(|&index, _| same_kind(index));
+        self.weather_widgets.retain(|&index, _| {
+            same_kind(index)
+                && old[index].location == new[index].location
+                && old[index].url == new[index].url
+                && old[index].weather_ms == new[index].weather_ms
+        });
+        self.layers.clear();
+    }
+
+    /// Blends two packed frames of this renderer's size: `from` at `progress`
+    /// 0, `to` at 1. Pixels switch in the boot dissolve's noise order, or
+    /// column by column from the left with `Wipe`.
+    pub fn transition(
+        &mut self,
+        from: &[u8],
+        to: &[u8],
+        progress: f32,
+        style: TransitionStyle,
+    ) -> &[u8] {
+        self.packed.clear();
+        self.packed.extend_from_slice(to);
+        if from.len() != to.len() {
+            return &self.packed;
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let bit = y * self.width + x;
+                let threshold = match style {
+                    // Scrambled so neighbouring pixels don't switch in stripes.
+                    TransitionStyle::Dissolve => {
+                        dissolve_noise(((bit as u32).wrapping_mul(2654435761) >> 16) as usize)
+                    }
+                    TransitionStyle::Wipe => (x as f32 + 0.5) / self.width as f32,
+                };
+                if threshold >= progress {
+                    let mask = 0x80 >> (bit % 8);
+                    self.packed[bit / 8] = (self.packed[bit / 8] & !mask) | (from[bit / 8] & mask);
+                }
+            }
         }
+        &self.packed
     }
 
     fn pick_random_transition(&mut self) {
@@ -125,41 +230,165 @@ impl DashboardRenderer {
         self.melt_seed = (tv.tv_sec as u32).wrapping_mul(1000000000).wrapping_add(tv.tv_nsec as u32);
     }
 
-    pub fn render(&mut self, config: &DashboardConfig, sample: &MetricsSample) -> Vec<u8> {
+    /// Pixel buffer of the most recently rendered frame, one byte per pixel.
+    #[allow(dead_code)]
+    pub fn pixels(&self) -> Vec<u8> {
+        self.canvas.pixels()
+    }
+
+    /// Renders one frame and returns it packed; the buffer is reused between calls.
+    pub fn render(&mut self, config: &DashboardConfig, sample: &MetricsSample) -> &[u8] {
+        self.frame_count = self.frame_count.wrapping_add(1);
         self.canvas.clear(config.display.background > 0);
 
         let elapsed = self.boot_started.elapsed();
         if elapsed < self.boot_duration {
             let progress = (elapsed.as_secs_f32() / self.boot_duration.as_secs_f32()).clamp(0.0, 1.0);
-            self.draw_boot_logo(progress);
-            return self.canvas.to_packed_bytes();
+            if self.boot_style == BootStyle::Gear {
+                self.draw_boot_logo(progress);
+            }
+            orient(&mut self.canvas, &config.display);
+            self.canvas.pack_into(&mut self.packed);
+            return &self.packed;
         }
 
-        for widget in &config.widgets {
+        // Forget series of widgets that were removed or disabled.
+        self.histories
+            .retain(|&(index, _), _| config.widgets.get(index).is_some_and(|w| w.enabled));
+
+        self.layers
+            .retain(|&index, _| config.widgets.get(index).is_some_and(|w| w.render_interval_ms.is_some()));
+
+        for (index, widget) in config.widgets.iter().enumerate() {
             if !widget.enabled {
                 continue;
             }
 
-            match widget.kind.as_str() {
-                "cpu" => self.draw_cpu(widget, sample),
-                "volume" => {
-                    self.update_volume_overlay(sample);
-                    self.draw_volume_clock_transition(widget, sample);
-                }
-                "memory" => self.draw_memory(widget, sample),
-                "network" => self.draw_network(widget, sample),
-                "keyboard" => self.draw_keyboard(widget, sample),
-                _ => {}
+            match widget.render_interval_ms.filter(|&ms| ms > 0) {
+                Some(interval_ms) => self.draw_widget_throttled(index, widget, sample, interval_ms),
+                None => self.draw_widget(index, widget, sample),
+            }
+
+            if self.alert_inverted(widget, sample) {
+                let p = &widget.position;
+                self.canvas.rect_fill_invert(p.x, p.y, p.w, p.h);
             }
         }
 
         self.draw_sine_wave_gap(config, sample);
         self.draw_mem_net_separator(config);
 
-        self.canvas.to_packed_bytes()
+        if let Some(period_ms) = config.burn_in_shift_ms.filter(|&ms| ms > 0) {
+            let (dx, dy) = burn_in_offset(self.boot_started.elapsed(), period_ms);
+            self.canvas.shift(dx, dy);
+        }
+
+        orient(&mut self.canvas, &config.display);
+        self.canvas.pack_into(&mut self.packed);
+        &self.packed
+    }
+
+    fn draw_widget(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        match widget.kind.as_str() {
+            "cpu" => self.draw_cpu(index, widget, sample),
+            "volume" => {
+                self.update_volume_overlay(sample);
+                self.draw_volume_clock_transition(widget, sample);
+            }
+            "memory" => self.draw_memory(index, widget, sample),
+            "network" if widget.mode.as_deref() == Some("graph") => {
+                self.draw_network_graph(index, widget, sample)
+            }
+            "network" => self.draw_network(widget, sample),
+            "keyboard" => self.draw_keyboard(widget, sample),
+            "gauge" => self.draw_gauge(widget, sample),
+            "command" => self.draw_command(index, widget, sample),
+            "media" => self.draw_media(widget, sample),
+            "gpu" => self.draw_gpu(widget, sample),
+            "sparkline" => self.draw_sparkline(index, widget, sample),
+            "analogclock" => self.draw_analog_clock(widget),
+            "topproc" => self.draw_top_process(widget, sample),
+            "cpufreq" => self.draw_cpu_freq(widget, sample),
+            "fan" => self.draw_fan(index, widget, sample),
+            "ping" => self.draw_ping(index, widget, sample),
+            "cputemp" => self.draw_cpu_temp(widget, sample),
+            "backlight" => self.draw_kbd_backlight(widget, sample),
+            "devicebattery" => self.draw_device_battery(index, widget, sample),
+            "barchart" => self.draw_barchart(widget, sample),
+            "weather" => self.draw_weather(index, widget),
+            _ => {}
+        }
+
+        // cpu and volume put a custom icon where their built-in one goes.
+        if !matches!(widget.kind.as_str(), "cpu" | "volume")
+            && let Some(bitmap) = &widget.icon_bitmap
+        {
+            let p = &widget.position;
+            self.canvas.draw_bitmap(p.x, p.y, bitmap, true);
+        }
+    }
+
+    /// Whether the widget's rect should be inverted this frame: always while it
+    /// alerts in `invert` mode, every other `alert_blink_frames` in `blink` mode.
+    fn alert_inverted(&self, widget: &Widget, sample: &MetricsSample) -> bool {
+        match widget.alert_mode() {
+            Some(AlertMode::Invert) => widget_alerting(widget, sample),
+            Some(AlertMode::Blink) => {
+                let phase = self.frame_count / widget.alert_blink_frames.max(1) as u64;
+                phase.is_multiple_of(2) && widget_alerting(widget, sample)
+            }
+            None => false,
+        }
+    }
+
+    /// Double-buffered draw for widgets with `render_interval_ms`: the widget
+    /// renders into its own off-screen layer only when the interval elapsed,
+    /// and the cached layer is blitted onto the frame every tick. Because the
+    /// frame canvas is still cleared each tick, unthrottled widgets and the
+    /// decorations keep animating while this one holds its pixels.
+    fn draw_widget_throttled(
+        &mut self,
+        index: usize,
+        widget: &Widget,
+        sample: &MetricsSample,
+        interval_ms: u32,
+    ) {
+        let interval = Duration::from_millis(interval_ms as u64);
+        let mut layer = self.layers.remove(&index);
+        let due = layer.as_ref().is_none_or(|l| l.drawn_at.elapsed() >= interval);
+
+        if due {
+            let mut canvas = layer
+                .take()
+                .map(|l| l.canvas)
+                .unwrap_or_else(|| Canvas::new(self.width, self.height));
+            canvas.clear(false);
+            std::mem::swap(&mut self.canvas, &mut canvas);
+            self.draw_widget(index, widget, sample);
+            std::mem::swap(&mut self.canvas, &mut canvas);
+            layer = Some(WidgetLayer {
+                canvas,
+                drawn_at: Instant::now(),
+            });
+        }
+
+        if let Some(layer) = layer {
+            self.canvas.blit(&layer.canvas, 0, 0, false);
+            self.layers.insert(index, layer);
+        }
+    }
+
+    /// True while something on screen is mid-animation or reacting to audio,
+    /// so the main loop should not back off to the idle refresh rate.
+    pub fn wants_fast_refresh(&self) -> bool {
+        self.boot_started.elapsed() < self.boot_duration
+            || self.show_volume_overlay
+            || self.volume_transition != self.volume_transition_target
+            || self.audio_active
     }
 
     fn draw_sine_wave_gap(&mut self, config: &DashboardConfig, sample: &MetricsSample) {
+        self.audio_active = false;
         let volume = match config.widgets.iter().find(|w| w.enabled && w.kind == "volume") {
             Some(w) => w,
             None => return,
@@ -196,7 +425,7 @@ impl DashboardRenderer {
         let max_amp = ((draw_bottom - draw_top) / 2).max(1) as f32;
         let level = (sample.audio_level / 100.0).clamp(0.0, 1.0);
         let silence_gate = 0.02f32;
-        let is_silent = sample.volume_percent <= 0.0 || sample.audio_waveform.is_empty() || level <= silence_gate;
+        let is_silent = sample.is_muted || sample.volume_percent <= 0.0 || sample.audio_waveform.is_empty() || level <= silence_gate;
 
         // Track silence duration
         if is_silent {
@@ -206,6 +435,7 @@ impl DashboardRenderer {
         } else {
             self.silence_start = None;
         }
+        self.audio_active = !is_silent;
 
         // Calculate idle sine blend (0.0 = flatline, 1.0 = full sine)
         let idle_blend = if let Some(start) = self.silence_start {
@@ -321,13 +551,7 @@ impl DashboardRenderer {
             0.0
         };
 
-        let keep = |seed: usize| -> bool {
-            if dissolve_t <= 0.0 {
-                return true;
-            }
-            let noise = ((seed * 37 + 17) % 100) as f32 / 100.0;
-            noise > dissolve_t
-        };
+        let keep = |seed: usize| -> bool { dissolve_t <= 0.0 || dissolve_noise(seed) > dissolve_t };
 
         let teeth = 12usize;
         let reveal = ((teeth as f32) * progress).ceil() as usize;
@@ -446,23 +670,281 @@ impl DashboardRenderer {
         }
     }
 
-    fn draw_cpu(&mut self, widget: &Widget, sample: &MetricsSample) {
-        self.draw_bar(
-            &widget.position,
-            sample.cpu_percent,
-            widget
-                .bar
-                .as_ref()
-                .map(|b| b.direction.as_str())
-                .unwrap_or("vertical"),
-            widget.bar.as_ref().map(|b| b.border).unwrap_or(false),
-        );
-        self.draw_cpu_icon(&widget.position);
+    /// Vertical bar with the chip icon, or a history graph when the widget has a
+    /// `graph` section; the graph only shows the icon with `show_icon`.
+    fn draw_cpu(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        if widget.graph.is_some() {
+            self.draw_history_graph(index, widget, sample.cpu_percent);
+            if widget.show_icon {
+                self.draw_cpu_icon(widget);
+            }
+            return;
+        }
+        self.draw_widget_bar(widget, sample.cpu_percent, "vertical", false);
+        self.draw_cpu_icon(widget);
+    }
+
+    /// Same bar as the CPU widget, without the chip icon.
+    fn draw_gpu(&mut self, widget: &Widget, sample: &MetricsSample) {
+        self.draw_widget_bar(widget, sample.gpu_percent, "vertical", false);
+    }
+
+    /// Condition icon on the left and the temperature in °C beside it. Fetching
+    /// happens on a background thread; until the first result the icon shows
+    /// "unknown" and no temperature is drawn.
+    fn draw_weather(&mut self, index: usize, widget: &Widget) {
+        let p = &widget.position;
+        let interval =
+            Duration::from_millis(widget.weather_ms.unwrap_or(config::DEFAULT_WEATHER_MS) as u64);
+        let (condition, temperature) = if widget.location.is_none() && widget.url.is_none() {
+            // The lookup shared with the clock follows this widget's interval.
+            self.weather.set_fetch_interval(interval);
+            self.weather.update();
+            (self.weather.condition, self.weather.temperature)
+        } else {
+            let cache = self.weather_widgets.entry(index).or_insert_with(|| {
+                WeatherCache::with_source(
+                    widget.location.as_deref().and_then(config::parse_location),
+                    widget.url.clone(),
+                    interval,
+                )
+            });
+            cache.update();
+            (cache.condition, cache.temperature)
+        };
+
+        self.weather_anim_phase = (self.weather_anim_phase + 0.1) % (TAU * 100.0);
+        let icon_size = 14;
+        self.draw_weather_icon(p.x, p.y + (p.h - icon_size) / 2, icon_size, condition);
+
+        if condition != WeatherCondition::Unknown {
+            let text = format!("{}C", temperature.round() as i32);
+            self.canvas
+                .draw_text_tiny(p.x + icon_size + 2, p.y + (p.h - 5) / 2, &text);
+        }
+    }
+
+    /// Busiest process name (truncated to fit) with a thin CPU-share bar below.
+    fn draw_top_process(&mut self, widget: &Widget, sample: &MetricsSample) {
+        let p = &widget.position;
+        let (name, percent) = &sample.top_process;
+        if name.is_empty() {
+            return;
+        }
+
+        let fits = ((p.w + 1) / 5).max(0) as usize;
+        let label: String = name.chars().take(fits).collect();
+        self.canvas.draw_text_tiny(p.x, p.y, &label);
+
+        if p.h >= 8 {
+            let filled = (p.w as f32 * percent / 100.0).round() as i32;
+            self.canvas.rect_border(p.x, p.y + p.h - 3, p.w, 3, true);
+            self.canvas.rect_fill(p.x, p.y + p.h - 3, filled, 3, true);
+        }
+    }
+
+    /// Average core clock in tiny text: `3.4G`, or `3400` with `mode: "mhz"`.
+    /// Nothing is drawn while the clock is unknown.
+    fn draw_cpu_freq(&mut self, widget: &Widget, sample: &MetricsSample) {
+        if sample.cpu_freq_mhz <= 0.0 {
+            return;
+        }
+        let text = if widget.mode.as_deref() == Some("mhz") {
+            format!("{}", sample.cpu_freq_mhz.round() as u32)
+        } else {
+            format!("{:.1}G", sample.cpu_freq_mhz / 1000.0)
+        };
+        let p = &widget.position;
+        self.canvas.draw_text_tiny(p.x, p.y, &text);
+    }
+
+    /// A 9×9 three-bladed fan on the left, spinning faster with the RPM, and the
+    /// RPM in tiny text beside it. Hidden while the fan reads 0 or is missing.
+    fn draw_fan(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        if sample.fan_rpm == 0 {
+            return;
+        }
+        // Capped below a third of a turn per frame, where the three blades
+        // would start to look like they turn backwards.
+        let step = (sample.fan_rpm as f32 / 3000.0 * 0.6).min(1.0);
+        let phase = self.fan_phases.entry(index).or_insert(0.0);
+        *phase = (*phase + step) % TAU;
+        let phase = *phase;
+
+        let p = &widget.position;
+        let (cx, cy) = (p.x + 4, p.y + p.h / 2);
+        self.canvas.circle(cx, cy, 4, true);
+        for blade in 0..3 {
+            let angle = phase + blade as f32 * TAU / 3.0;
+            let (x, y) = (
+                cx + (angle.cos() * 3.0).round() as i32,
+                cy + (angle.sin() * 3.0).round() as i32,
+            );
+            self.canvas.line(cx, cy, x, y, true);
+        }
+        self.canvas
+            .draw_text_tiny(p.x + 11, cy - 2, &sample.fan_rpm.to_string());
+    }
+
+    /// Latest round trip as `NNms` (`--` before the first reply or after a
+    /// timeout), and below it a sparkline of recent pings scaled to the slowest
+    /// on screen. Timeouts leave a gap in the sparkline.
+    fn draw_ping(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        let p = widget.position;
+        let rtts = sample.ping_rtts.get(&(index + self.widget_offset));
+        let text = match rtts.and_then(|r| r.back().copied().flatten()) {
+            Some(ms) => format!("{}ms", ms.round() as u32),
+            None => "--".to_string(),
+        };
+        self.canvas.draw_text_tiny(p.x, p.y, &text);
+
+        // Text is 5px tall; the sparkline needs a few rows below a 1px gap.
+        let (graph_y, graph_h) = (p.y + 6, p.h - 6);
+        let Some(rtts) = rtts else {
+            return;
+        };
+        if graph_h < 3 || p.w <= 0 {
+            return;
+        }
+        let shown = rtts.len().min(p.w as usize);
+        let recent = rtts.iter().skip(rtts.len() - shown);
+        let peak = recent.clone().flatten().fold(1.0f32, |m, &v| m.max(v));
+        let start_x = p.x + p.w - shown as i32;
+        for (i, rtt) in recent.enumerate() {
+            if let Some(ms) = rtt {
+                let rise = (ms / peak * (graph_h - 1) as f32).round() as i32;
+                self.canvas
+                    .set(start_x + i as i32, graph_y + graph_h - 1 - rise, true);
+            }
+        }
+    }
+
+    /// Package temperature as `54C`, or with `mode: "cores"` a `draw_bar_chart`
+    /// of the core temperatures scaled over `min`/`max`.
+    fn draw_cpu_temp(&mut self, widget: &Widget, sample: &MetricsSample) {
+        let p = widget.position;
+        if widget.mode.as_deref() != Some("cores") {
+            if sample.cpu_temp > 0.0 {
+                let text = format!("{}C", sample.cpu_temp.round() as i32);
+                self.canvas.draw_text_tiny(p.x, p.y, &text);
+            }
+            return;
+        }
+
+        let (min, max) = widget.range();
+        self.draw_bar_chart(&p, &sample.core_temps, min, max);
+    }
+
+    /// `barchart`: one vertical bar per value of `source` (`cpucores`, the
+    /// default, or `coretemps`), at most `max_bars` of them.
+    fn draw_barchart(&mut self, widget: &Widget, sample: &MetricsSample) {
+        let values = match widget.source.as_deref() {
+            Some("coretemps") => &sample.core_temps,
+            _ => &sample.cpu_core_percents,
+        };
+        let count = widget.max_bars.map_or(values.len(), |n| n.min(values.len()));
+        let (min, max) = widget.range();
+        self.draw_bar_chart(&widget.position, &values[..count], min, max);
+    }
+
+    /// Bottom-up bars for `values` scaled over `min`..`max`, split evenly
+    /// across the width with 1px gaps. Values that don't fit at 1px each are
+    /// left out.
+    fn draw_bar_chart(&mut self, p: &Position, values: &[f32], min: f32, max: f32) {
+        let count = values.len().min(((p.w + 1) / 2).max(0) as usize);
+        if count == 0 || p.h <= 0 {
+            return;
+        }
+        // Spread the leftover columns over the first bars so the row spans p.w.
+        let bar_space = p.w - (count as i32 - 1);
+        let (bar_w, extra) = (bar_space / count as i32, bar_space % count as i32);
+        let mut x = p.x;
+        for (i, &value) in values[..count].iter().enumerate() {
+            let w = bar_w + i32::from((i as i32) < extra);
+            let filled = (range_percent(value, min, max) / 100.0 * p.h as f32).round() as i32;
+            self.canvas
+                .rect_fill(x, p.y + p.h - filled, w, filled, true);
+            x += w + 1;
+        }
+    }
+
+    /// Keyboard backlight level as a bar, or with `mode: "sun"` a sun whose
+    /// disc fills from the bottom. Hidden when there is no backlight LED.
+    fn draw_kbd_backlight(&mut self, widget: &Widget, sample: &MetricsSample) {
+        let Some(level) = sample.kbd_backlight else {
+            return;
+        };
+        if widget.mode.as_deref() != Some("sun") {
+            self.draw_widget_bar(widget, level, "horizontal", true);
+            return;
+        }
+
+        // Rays take 3px around the disc when the widget has room for them.
+        let p = widget.position;
+        let size = p.w.min(p.h);
+        let (cx, cy) = (p.x + (size - 1) / 2, p.y + (size - 1) / 2);
+        let with_rays = size >= 11;
+        let r = if with_rays { (size - 1) / 2 - 3 } else { (size - 1) / 2 };
+        if r < 1 {
+            return;
+        }
+        self.canvas.circle(cx, cy, r, true);
+        let filled = (level / 100.0 * (2 * r + 1) as f32).round() as i32;
+        for dy in -r..=r {
+            if r - dy >= filled {
+                continue;
+            }
+            for dx in -r..=r {
+                if dx * dx + dy * dy <= r * r {
+                    self.canvas.set(cx + dx, cy + dy, true);
+                }
+            }
+        }
+        if with_rays {
+            for ray in 0..8 {
+                let angle = ray as f32 * TAU / 8.0;
+                let (cos, sin) = (angle.cos(), angle.sin());
+                let at = |d: f32| {
+                    (cx + (cos * d).round() as i32, cy + (sin * d).round() as i32)
+                };
+                let ((x0, y0), (x1, y1)) = (at((r + 2) as f32), at((r + 3) as f32));
+                self.canvas.line(x0, y0, x1, y1, true);
+            }
+        }
+    }
+
+    /// Battery outline filling the widget, with a 2px terminal on the right
+    /// and the charge as a fill inside. Hidden while the device is missing.
+    fn draw_device_battery(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        let Some(&percent) = sample.device_batteries.get(&(index + self.widget_offset)) else {
+            return;
+        };
+        let p = widget.position;
+        if p.w < 8 || p.h < 5 {
+            return;
+        }
+        let body_w = p.w - 2;
+        self.canvas.rect_border(p.x, p.y, body_w, p.h, true);
+        let nub_y = p.y + p.h / 3;
+        self.canvas
+            .rect_fill(p.x + body_w, nub_y, 2, p.h - 2 * (nub_y - p.y), true);
+        // 1px gap between the outline and the fill.
+        let inner_w = body_w - 4;
+        let filled = (inner_w as f32 * percent.clamp(0.0, 100.0) / 100.0).round() as i32;
+        self.canvas.rect_fill(p.x + 2, p.y + 2, filled, p.h - 4, true);
     }
 
     /// Draws a tiny CPU chip icon (8×9) at the top of the widget,
     /// 2px from top border, using invert for visibility.
-    fn draw_cpu_icon(&mut self, pos: &Position) {
+    fn draw_cpu_icon(&mut self, widget: &Widget) {
+        let pos = &widget.position;
+        if let Some(bitmap) = &widget.icon_bitmap {
+            let icon_w = bitmap.first().map_or(0, Vec::len) as i32;
+            self.canvas
+                .draw_bitmap(pos.x + (pos.w - icon_w) / 2, pos.y + 2, bitmap, true);
+            return;
+        }
+
         // 8 wide × 9 tall chip icon
         #[rustfmt::skip]
         const CHIP: [[u8; 8]; 9] = [
@@ -495,27 +977,26 @@ impl DashboardRenderer {
         let muted_now = sample.is_muted;
         let state_now = (vol_now, muted_now);
 
-        if let Some(prev) = self.prev_volume_state {
-            if state_now != prev {
-                // Volume or mute state changed → show overlay
-                self.show_volume_overlay = true;
-                self.volume_overlay_start = Some(Instant::now());
-                self.volume_transition_target = 1.0; // transition to volume
-                self.pick_random_transition();
-            }
+        if let Some(prev) = self.prev_volume_state
+            && state_now != prev
+        {
+            // Volume or mute state changed → show overlay
+            self.show_volume_overlay = true;
+            self.volume_overlay_start = Some(Instant::now());
+            self.volume_transition_target = 1.0; // transition to volume
+            self.pick_random_transition();
         }
         self.prev_volume_state = Some(state_now);
 
         // Auto-hide volume overlay after 3 seconds
-        if self.show_volume_overlay {
-            if let Some(start) = self.volume_overlay_start {
-                if start.elapsed() > Duration::from_secs(3) {
-                    self.show_volume_overlay = false;
-                    self.volume_overlay_start = None;
-                    self.volume_transition_target = 0.0; // transition back to clock
-                    self.pick_random_transition();
-                }
-            }
+        if self.show_volume_overlay
+            && let Some(start) = self.volume_overlay_start
+            && start.elapsed() > Duration::from_secs(3)
+        {
+            self.show_volume_overlay = false;
+            self.volume_overlay_start = None;
+            self.volume_transition_target = 0.0; // transition back to clock
+            self.pick_random_transition();
         }
 
         // Smoothly interpolate transition progress toward target
@@ -613,7 +1094,7 @@ impl DashboardRenderer {
                     TransitionType::Scanlines => {
                         // Alternating horizontal lines reveal at different rates
                         let row = (y - p.y) as usize;
-                        let offset = if row % 2 == 0 { 0.0 } else { 0.3 };
+                        let offset = if row.is_multiple_of(2) { 0.0 } else { 0.3 };
                         let threshold = offset + (1.0 - offset) * (x - p.x) as f32 / p.w as f32;
                         progress > threshold
                     }
@@ -638,20 +1119,52 @@ impl DashboardRenderer {
         }
     }
 
+    /// Round face with 12 ticks and hour/minute hands, plus a second hand with
+    /// `show_seconds`. Sized to the widget's shorter side.
+    fn draw_analog_clock(&mut self, widget: &Widget) {
+        let p = &widget.position;
+        let r = (p.w.min(p.h) - 1) / 2;
+        if r < 4 {
+            return;
+        }
+        let cx = p.x + p.w / 2;
+        let cy = p.y + p.h / 2;
+        // Clockwise from 12 o'clock, so screen y points down.
+        let point = |turns: f32, len: f32| {
+            let angle = turns * TAU;
+            (
+                cx + (angle.sin() * len).round() as i32,
+                cy - (angle.cos() * len).round() as i32,
+            )
+        };
+
+        self.canvas.circle(cx, cy, r, true);
+        for tick in 0..12 {
+            // Quarter-hour ticks are longer on faces big enough to show it.
+            let inner = if tick % 3 == 0 && r >= 8 { r - 3 } else { r - 2 };
+            let (x0, y0) = point(tick as f32 / 12.0, inner as f32);
+            let (x1, y1) = point(tick as f32 / 12.0, (r - 1) as f32);
+            self.canvas.line(x0, y0, x1, y1, true);
+        }
+
+        let (hours, minutes, seconds) = local_time();
+        let minute_turns = (minutes as f32 + seconds as f32 / 60.0) / 60.0;
+        let hour_turns = ((hours % 12) as f32 + minutes as f32 / 60.0) / 12.0;
+
+        let (hx, hy) = point(hour_turns, r as f32 * 0.5);
+        self.canvas.line_thick(cx, cy, hx, hy, if r >= 8 { 2 } else { 1 }, true);
+        let (mx, my) = point(minute_turns, r as f32 * 0.75);
+        self.canvas.line(cx, cy, mx, my, true);
+        if widget.show_seconds {
+            let (sx, sy) = point(seconds as f32 / 60.0, r as f32 * 0.85);
+            self.canvas.line(cx, cy, sx, sy, true);
+        }
+    }
+
     fn draw_clock(&mut self, widget: &Widget) {
         let p = &widget.position;
 
-        // Get current time
-        let now = {
-            let mut tv = libc::timespec { tv_sec: 0, tv_nsec: 0 };
-            unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, &mut tv); } //LOOOOOOL
-            tv.tv_sec
-        };
-        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
-        unsafe { libc::localtime_r(&now, &mut tm); } //DRIVER UNUSABLE. RUST WAS A MISTAKE. AI WILL TURN YOUR PC INTO A TIMEBOMB.
-        let hours = tm.tm_hour as u32;
-        let minutes = tm.tm_min as u32;
-        let seconds = tm.tm_sec as u32;
+        let (hours, minutes, seconds) = local_time();
 
         let blink_elapsed_ms = self.colon_blink.elapsed().as_millis();
         let colon_on = (blink_elapsed_ms % 1000) < 500;
@@ -721,7 +1234,7 @@ impl DashboardRenderer {
         let icon_y = p.y + 4;
         let icon_size = 14; // 14x14 pixel area for weather icon
         
-        self.draw_weather_icon(icon_x, icon_y, icon_size);
+        self.draw_weather_icon(icon_x, icon_y, icon_size, self.weather.condition);
 
         // Draw HH:MM character by character with tighter spacing (24h military format)
         let h_str = format!("{:02}", hours);
@@ -772,9 +1285,8 @@ impl DashboardRenderer {
         }
     }
 
-    fn draw_weather_icon(&mut self, x: i32, y: i32, size: i32) {
+    fn draw_weather_icon(&mut self, x: i32, y: i32, size: i32, condition: WeatherCondition) {
         let phase = self.weather_anim_phase;
-        let condition = self.weather.condition;
 
         match condition {
             WeatherCondition::Sunny => self.draw_sun_icon(x, y, size, phase),
@@ -1151,18 +1663,25 @@ impl DashboardRenderer {
         let current_volume = (sample.volume_percent.round() as i32).clamp(0, 100);
         self.update_volume_animation(current_volume);
 
+        // Muted: empty bar, but the digits keep showing the level. The digits
+        // are the value too, so `show_value` is left out.
+        let bar = widget.bar.as_ref();
+        let (min, max) = bar.map_or((0.0, 100.0), |b| (b.min, b.max));
         self.draw_bar(
             &widget.position,
-            sample.volume_percent,
-            widget
-                .bar
-                .as_ref()
-                .map(|b| b.direction.as_str())
-                .unwrap_or("horizontal"),
-            widget.bar.as_ref().map(|b| b.border).unwrap_or(true),
+            if sample.is_muted { 0.0 } else { range_percent(sample.volume_percent, min, max) },
+            bar.map(|b| b.direction.as_str()).unwrap_or("horizontal"),
+            bar.map_or(1, BarConfig::border_px),
+            bar.map_or(0, BarConfig::segment_count),
         );
 
-        if widget.show_icon {
+        if widget.show_icon
+            && let Some(bitmap) = &widget.icon_bitmap
+        {
+            let p = &widget.position;
+            let top = p.y + (p.h - bitmap.len() as i32) / 2;
+            self.canvas.draw_bitmap(p.x + 2, top, bitmap, true);
+        } else if widget.show_icon {
             let p = &widget.position;
             let cx = p.x + 2;                    // left edge of icon
             let top = p.y + 3;                    // 2px from border (1px border + 2px gap)
@@ -1180,9 +1699,19 @@ impl DashboardRenderer {
             self.canvas.line_invert(cx + body_w, cy + body_half, cx + body_w + 3, bot);
             self.canvas.line_invert(cx + body_w + 3, top, cx + body_w + 3, bot);
 
+            // Muted: an × where the waves would be, so 0% and mute differ
+            if sample.is_muted {
+                let x0 = cx + body_w + 5;
+                let r = (half / 2).clamp(1, 3);
+                self.canvas.line_invert(x0, cy - r, x0 + 2 * r, cy + r);
+                self.canvas.line_invert(x0, cy + r, x0 + 2 * r, cy - r);
+                // Both strokes toggled the crossing pixel; restore it
+                self.canvas.invert(x0 + r, cy);
+            }
+
             // Sound wave arcs — count based on volume level
-            // 0% (mute) = 0 waves, 1-33% = 1, 34-66% = 2, 67-100% = 3
-            let vol = sample.volume_percent;
+            // 0% or muted = 0 waves, 1-33% = 1, 34-66% = 2, 67-100% = 3
+            let vol = if sample.is_muted { 0.0 } else { sample.volume_percent };
             let wave_count = if vol <= 0.0 { 0 } else if vol <= 33.0 { 1 } else if vol <= 66.0 { 2 } else { 3 };
 
             if wave_count >= 1 {
@@ -1209,7 +1738,7 @@ impl DashboardRenderer {
 
         let scale = 2;
         let p = &widget.position;
-        let border = widget.bar.as_ref().map(|b| b.border).unwrap_or(true);
+        let border = widget.bar.as_ref().map_or(1, BarConfig::border_px);
         let char_w = 5 * scale;
         let text_px = 4 * char_w; // 3 digits + %
         let text_h = 5 * scale;
@@ -1221,10 +1750,10 @@ impl DashboardRenderer {
         }
         let base_y = p.y + ((p.h - text_h) / 2).max(0);
 
-        let clip_x = if border { p.x + 1 } else { p.x };
-        let clip_y = if border { p.y + 1 } else { p.y };
-        let clip_w = if border { p.w - 2 } else { p.w };
-        let clip_h = if border { p.h - 2 } else { p.h };
+        let clip_x = p.x + border;
+        let clip_y = p.y + border;
+        let clip_w = p.w - 2 * border;
+        let clip_h = p.h - 2 * border;
 
         let text_clip_y = base_y.max(clip_y);
         let text_clip_bottom = (base_y + text_h - 1).min(clip_y + clip_h - 1);
@@ -1252,15 +1781,16 @@ impl DashboardRenderer {
                 let to_ch = new_digits[i];
 
                 if from_ch == to_ch {
-                    self.canvas.draw_char_scaled_invert_clipped(
+                    self.canvas.draw_text_scaled_clipped(
                         slot_x,
                         base_y,
-                        to_ch,
+                        to_ch.encode_utf8(&mut [0; 4]),
                         scale,
                         slot_clip_x,
                         text_clip_y,
                         slot_clip_w,
                         text_clip_h,
+                        true,
                     );
                     continue;
                 }
@@ -1268,29 +1798,31 @@ impl DashboardRenderer {
                 if self.vol_anim_step < leave_frames {
                     let step = self.vol_anim_step as i32 + 1;
                     let offset = (step * text_h) / leave_frames as i32;
-                    self.canvas.draw_char_scaled_invert_clipped(
+                    self.canvas.draw_text_scaled_clipped(
                         slot_x,
                         base_y + dir * offset,
-                        from_ch,
+                        from_ch.encode_utf8(&mut [0; 4]),
                         scale,
                         slot_clip_x,
                         text_clip_y,
                         slot_clip_w,
                         text_clip_h,
+                        true,
                     );
                 } else {
                     let step = (self.vol_anim_step - leave_frames) as i32 + 1;
                     let enter_frames = enter_frames.max(1) as i32;
                     let offset = text_h - (step * text_h) / enter_frames;
-                    self.canvas.draw_char_scaled_invert_clipped(
+                    self.canvas.draw_text_scaled_clipped(
                         slot_x,
                         base_y - dir * offset,
-                        to_ch,
+                        to_ch.encode_utf8(&mut [0; 4]),
                         scale,
                         slot_clip_x,
                         text_clip_y,
                         slot_clip_w,
                         text_clip_h,
+                        true,
                     );
                 }
             }
@@ -1300,15 +1832,16 @@ impl DashboardRenderer {
             let percent_clip_x = percent_x.max(clip_x);
             let percent_clip_right = (percent_x + char_w - 1).min(clip_x + clip_w - 1);
             let percent_clip_w = (percent_clip_right - percent_clip_x + 1).max(0);
-            self.canvas.draw_char_scaled_invert_clipped(
+            self.canvas.draw_text_scaled_clipped(
                 percent_x,
                 base_y,
-                '%',
+                "%",
                 scale,
                 percent_clip_x,
                 text_clip_y,
                 percent_clip_w,
                 text_clip_h,
+                true,
             );
         } else {
             let shown = self.volume_display.unwrap_or(current_volume);
@@ -1318,30 +1851,32 @@ impl DashboardRenderer {
                 let slot_clip_x = slot_x.max(clip_x);
                 let slot_clip_right = (slot_x + char_w - 1).min(clip_x + clip_w - 1);
                 let slot_clip_w = (slot_clip_right - slot_clip_x + 1).max(0);
-                self.canvas.draw_char_scaled_invert_clipped(
+                self.canvas.draw_text_scaled_clipped(
                     slot_x,
                     base_y,
-                    *ch,
+                    ch.encode_utf8(&mut [0; 4]),
                     scale,
                     slot_clip_x,
                     text_clip_y,
                     slot_clip_w,
                     text_clip_h,
+                    true,
                 );
             }
             let percent_x = text_x + 3 * char_w;
             let percent_clip_x = percent_x.max(clip_x);
             let percent_clip_right = (percent_x + char_w - 1).min(clip_x + clip_w - 1);
             let percent_clip_w = (percent_clip_right - percent_clip_x + 1).max(0);
-            self.canvas.draw_char_scaled_invert_clipped(
+            self.canvas.draw_text_scaled_clipped(
                 percent_x,
                 base_y,
-                '%',
+                "%",
                 scale,
                 percent_clip_x,
                 text_clip_y,
                 percent_clip_w,
                 text_clip_h,
+                true,
             );
         }
 
@@ -1358,7 +1893,178 @@ impl DashboardRenderer {
         ]
     }
 
-    fn draw_memory(&mut self, widget: &Widget, sample: &MetricsSample) {
+    /// History graph by default; a bar instead when the widget has a `bar`
+    /// section and no `graph` one. Both show the percentage in the top-right,
+    /// or used/total GiB with `mode: "absolute"`.
+    fn draw_memory(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        let as_bar = widget.bar.is_some() && widget.graph.is_none();
+        if as_bar {
+            self.draw_widget_bar(widget, sample.mem_percent, "horizontal", false);
+            if widget.bar.as_ref().is_some_and(|b| b.show_value) {
+                return;
+            }
+        } else {
+            self.draw_history_graph(index, widget, sample.mem_percent);
+        }
+
+        // Right-aligned with a 1px margin.
+        let p = &widget.position;
+        let text = if widget.mode.as_deref() == Some("absolute") {
+            format!("{:.1}/{:.0}G", sample.mem_used_gb, sample.mem_total_gb)
+        } else {
+            format!("{:>3}%", sample.mem_percent.round() as i32)
+        };
+        if as_bar {
+            // Inverted so it stays readable once the fill reaches it.
+            let text_x = p.x + p.w - 1 - Canvas::text_width(&text, 1);
+            self.canvas
+                .draw_text_scaled_invert(text_x, p.y + 1, &text, 1);
+        } else {
+            self.canvas
+                .draw_text_scaled_aligned(p.x, p.y + 1, p.w - 1, &text, 1, TextAlign::Right);
+        }
+    }
+
+    /// Ring gauge: a bezel circle with a thick arc sweeping clockwise from 12 o'clock
+    /// proportional to the source metric, and the percentage centred inside.
+    fn draw_gauge(&mut self, widget: &Widget, sample: &MetricsSample) {
+        let p = &widget.position;
+        let raw = metric_value(sample, widget.source.as_deref().unwrap_or("cpu"));
+        let (min, max) = widget.range();
+        let value = range_percent(raw, min, max);
+
+        let radius = p.w.min(p.h) / 2 - 1;
+        if radius < 4 {
+            return;
+        }
+        let cx = p.x + p.w / 2;
+        let cy = p.y + p.h / 2;
+
+        self.canvas.circle(cx, cy, radius, true);
+
+        // Arc drawn by angle steps like the boot ring; two radii for a 2px band.
+        let steps = (TAU * radius as f32).ceil() as i32 * 2;
+        let lit = ((value / 100.0) * steps as f32).round() as i32;
+        for i in 0..lit {
+            let a = (i as f32 / steps as f32) * TAU - TAU / 4.0;
+            for r in [radius - 2, radius - 3] {
+                let x = cx + (a.cos() * r as f32).round() as i32;
+                let y = cy + (a.sin() * r as f32).round() as i32;
+                self.canvas.set(x, y, true);
+            }
+        }
+
+        let scale = if radius >= 24 { 2 } else { 1 };
+        let text = range_label(raw, min, max);
+        let text_w = text.len() as i32 * 5 * scale - scale;
+        let text_h = 5 * scale;
+        self.canvas
+            .draw_text_scaled(cx - text_w / 2, cy - text_h / 2, &text, scale);
+    }
+
+    /// Command output on one line (see `draw_marquee`), or word-wrapped with `wrap`.
+    fn draw_command(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        let p = &widget.position;
+        let Some(output) = sample.command_output.get(&(index + self.widget_offset)) else {
+            return;
+        };
+        let text: Vec<char> = output
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .collect();
+        if text.is_empty() {
+            return;
+        }
+
+        if widget.wrap {
+            self.canvas.draw_text_wrapped(p.x, p.y, p.w, p.h, output, 1);
+            return;
+        }
+        self.draw_marquee(p, &text);
+    }
+
+    /// "Artist - Title" of the active player, scrolling (or wrapping, with `wrap`)
+    /// when it overflows, with a 1px progress bar along the bottom when the track length is known.
+    fn draw_media(&mut self, widget: &Widget, sample: &MetricsSample) {
+        let p = &widget.position;
+        let text: Vec<char> = sample.media_title.chars().collect();
+        if text.is_empty() {
+            return;
+        }
+
+        let text_rect = if sample.media_length > 0.0 {
+            Position {
+                h: p.h - 2,
+                ..*p
+            }
+        } else {
+            *p
+        };
+        if widget.wrap {
+            self.canvas.draw_text_wrapped(
+                text_rect.x,
+                text_rect.y,
+                text_rect.w,
+                text_rect.h,
+                &sample.media_title,
+                1,
+            );
+        } else {
+            self.draw_marquee(&text_rect, &text);
+        }
+        if sample.media_length <= 0.0 {
+            return;
+        }
+
+        let progress = (sample.media_position / sample.media_length).clamp(0.0, 1.0);
+        let filled = (p.w as f64 * progress).round() as i32;
+        self.canvas.rect_fill(p.x, p.y + p.h - 1, filled, 1, true);
+    }
+
+    /// One line of tiny-font text, vertically centred in `p`. Text wider than
+    /// the rect scrolls left a character at a time and wraps around.
+    fn draw_marquee(&mut self, p: &Position, text: &[char]) {
+        let y = p.y + (p.h - 5) / 2;
+        let fits = ((p.w + 1) / 5).max(0) as usize;
+        if text.len() <= fits {
+            let line: String = text.iter().collect();
+            self.canvas.draw_text_tiny(p.x, y, &line);
+            return;
+        }
+
+        // Three blank columns separate the tail from the wrapped-around head.
+        let period = text.len() + 3;
+        let offset = (self.boot_started.elapsed().as_millis() / 250) as usize % period;
+        let line: String = (0..fits)
+            .map(|i| text.get((offset + i) % period).copied().unwrap_or(' '))
+            .collect();
+        self.canvas.draw_text_tiny(p.x, y, &line);
+    }
+
+    /// One sample per column, newest on the right, drawn as a single pixel at
+    /// its height within `min`/`max`. No fill, unlike `draw_graph`.
+    fn draw_sparkline(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        let p = widget.position;
+        if p.w <= 0 || p.h <= 0 {
+            return;
+        }
+        let (min, max) = widget.range();
+        let value = metric_value(sample, widget.source.as_deref().unwrap_or("cpu"));
+        let history = self.push_history(index, Series::Main, value, p.w as usize);
+
+        let start_x = p.x + p.w - history.len() as i32;
+        for (i, &v) in history.iter().enumerate() {
+            let rise = (range_percent(v, min, max) / 100.0 * (p.h - 1) as f32).round() as i32;
+            self.canvas.set(start_x + i as i32, p.y + p.h - 1 - rise, true);
+        }
+        self.histories.insert((index, Series::Main), history);
+    }
+
+    /// Appends `value` to the widget's history (`graph.history` samples, default
+    /// one per column) and draws it with `draw_graph`.
+    fn draw_history_graph(&mut self, index: usize, widget: &Widget, value: f32) {
         let history_len = widget
             .graph
             .as_ref()
@@ -1366,38 +2072,114 @@ impl DashboardRenderer {
             .unwrap_or(widget.position.w.max(1) as usize)
             .max(2);
 
-        self.mem_history.push_back(sample.mem_percent);
-        while self.mem_history.len() > history_len {
-            self.mem_history.pop_front();
+        let history = self.push_history(index, Series::Main, value, history_len);
+        // Autoscale: the top is the buffer's peak plus 10% headroom.
+        let full = if widget.graph.as_ref().is_some_and(|g| g.autoscale) {
+            let peak = history.iter().fold(0.0f32, |m, &v| m.max(v)) * 1.1;
+            if peak > 0.0 { peak } else { 1.0 }
+        } else {
+            100.0
+        };
+        if let Some(graph) = &widget.graph {
+            self.draw_gridlines(&widget.position, &graph.gridlines);
         }
+        self.draw_graph(&widget.position, &history, full);
+        self.histories.insert((index, Series::Main), history);
+    }
 
-        let history = self.mem_history.clone();
-        self.draw_graph(&widget.position, &history);
-        let text = format!("{:>3}%", sample.mem_percent.round() as i32);
-        let char_w = 5; // tiny font width
-        let text_px = text.len() as i32 * char_w;
-        let text_x = widget.position.x + widget.position.w - text_px - 1;
-        self.canvas
-            .draw_text_tiny(text_x, widget.position.y + 1, &text);
+    /// Appends `value` to the widget's series, trims it to `len`, and hands the
+    /// buffer out so it can be drawn while `self` is borrowed mutably. Callers
+    /// put it back into `histories` afterwards.
+    fn push_history(
+        &mut self,
+        index: usize,
+        series: Series,
+        value: f32,
+        len: usize,
+    ) -> VecDeque<f32> {
+        let mut history = self.histories.remove(&(index, series)).unwrap_or_default();
+        history.push_back(value);
+        while history.len() > len {
+            history.pop_front();
+        }
+        history
     }
 
     fn draw_network(&mut self, widget: &Widget, sample: &MetricsSample) {
         let p = &widget.position;
-        let down = human_speed(sample.net_down_bps);
-        let up = human_speed(sample.net_up_bps);
+        let bits = widget.units.as_deref() == Some("bits");
+        let (down_bps, up_bps) = network_rates(widget, sample);
+        let (up_val, up_unit) = human_speed(up_bps, bits);
+        let (dn_val, dn_unit) = human_speed(down_bps, bits);
+
+        // Two 5px rows with at least 1px between them, spread over the height
+        // (rows at +1 and +10 on the usual 19px widget). Below 11px only the
+        // download row fits, so it's centred on its own.
+        if p.h < 11 {
+            let y = p.y + (p.h - 5).max(0) / 2;
+            self.draw_network_row(p, y, &format!("D {dn_val}"), dn_unit);
+            return;
+        }
+        let margin = if p.h >= 13 { 1 } else { 0 };
+        let up_y = p.y + margin;
+        let down_y = up_y + ((p.h - 2 * margin + 1) / 2).max(6);
+        self.draw_network_row(p, up_y, &format!("U {up_val}"), up_unit);
+        self.draw_network_row(p, down_y, &format!("D {dn_val}"), dn_unit);
+    }
 
-        let char_w = 5; // tiny font: 4px glyph + 1px gap
-        let right_edge = p.x + p.w - char_w; // unit char right-aligned
+    /// `mode: "graph"`: download (solid) and upload (dotted) over time, one
+    /// sample per column and scaled to the highest rate still on screen. A
+    /// "D"/"U" legend takes the top row when the widget is at least 14px tall.
+    fn draw_network_graph(&mut self, index: usize, widget: &Widget, sample: &MetricsSample) {
+        let p = widget.position;
+        if p.w < 2 || p.h < 2 {
+            return;
+        }
+        let (down_bps, up_bps) = network_rates(widget, sample);
+        let len = p.w as usize;
+        let down = self.push_history(index, Series::Main, down_bps as f32, len);
+        let up = self.push_history(index, Series::Upload, up_bps as f32, len);
+
+        let legend = p.h >= 14;
+        if legend {
+            self.canvas.draw_text_tiny(p.x + 1, p.y, "D");
+            self.canvas.line(p.x + 6, p.y + 2, p.x + 10, p.y + 2, true);
+            self.canvas.draw_text_tiny(p.x + 14, p.y, "U");
+            for x in (p.x + 19..=p.x + 23).step_by(2) {
+                self.canvas.set(x, p.y + 2, true);
+            }
+        }
+        let top = if legend { p.y + 6 } else { p.y };
+        let bottom = p.y + p.h - 1;
 
-        // Split value and unit (unit is always last char)
-        let (up_val, up_unit) = up.split_at(up.len() - 1);
-        let (dn_val, dn_unit) = down.split_at(down.len() - 1);
+        let peak = down.iter().chain(&up).fold(1.0f32, |m, &v| m.max(v));
+        let y_of = |v: f32| bottom - (v / peak * (bottom - top) as f32).round() as i32;
+        let start_x = p.x + p.w - down.len() as i32;
 
-        self.canvas.draw_text_tiny(p.x + 1, p.y + 1, &format!("U {up_val}"));
-        self.canvas.draw_text_tiny(right_edge, p.y + 1, up_unit);
+        for (i, (&down_bps, &up_bps)) in down.iter().zip(&up).enumerate() {
+            let x = start_x + i as i32;
+            let (down_y, up_y) = (y_of(down_bps), y_of(up_bps));
+            // Solid: join each sample to the previous one so steep rises stay connected.
+            if i > 0 {
+                self.canvas.line(x - 1, y_of(down[i - 1]), x, down_y, true);
+            } else {
+                self.canvas.set(x, down_y, true);
+            }
+            // Dotted: every other column only.
+            if (x - p.x) % 2 == 0 {
+                self.canvas.set(x, up_y, true);
+            }
+        }
+
+        self.histories.insert((index, Series::Main), down);
+        self.histories.insert((index, Series::Upload), up);
+    }
 
-        self.canvas.draw_text_tiny(p.x + 1, p.y + 10, &format!("D {dn_val}"));
-        self.canvas.draw_text_tiny(right_edge, p.y + 10, dn_unit);
+    /// One `network` row: the value on the left, its unit right-aligned.
+    fn draw_network_row(&mut self, p: &Position, y: i32, value: &str, unit: &str) {
+        self.canvas.draw_text_tiny(p.x + 1, y, value);
+        self.canvas
+            .draw_text_scaled_aligned(p.x, y, p.w, unit, 1, TextAlign::Right);
     }
 
     fn draw_keyboard(&mut self, widget: &Widget, sample: &MetricsSample) {
@@ -1777,89 +2559,259 @@ impl DashboardRenderer {
         }
     }
 
-    fn draw_bar(&mut self, pos: &Position, percent: f32, direction: &str, border: bool) {
+    /// `draw_bar` with the widget's `bar` settings (or the given defaults),
+    /// mapping `value` through the bar's `min`/`max`, plus the value on top
+    /// when `show_value` is set.
+    fn draw_widget_bar(
+        &mut self,
+        widget: &Widget,
+        value: f32,
+        default_direction: &str,
+        default_border: bool,
+    ) {
+        let bar = widget.bar.as_ref();
+        let (min, max) = bar.map_or((0.0, 100.0), |b| (b.min, b.max));
+        self.draw_bar(
+            &widget.position,
+            range_percent(value, min, max),
+            bar.map(|b| b.direction.as_str()).unwrap_or(default_direction),
+            bar.map_or(default_border as i32, BarConfig::border_px),
+            bar.map_or(0, BarConfig::segment_count),
+        );
+        if bar.is_some_and(|b| b.show_value) {
+            self.draw_bar_value(&widget.position, &range_label(value, min, max));
+        }
+    }
+
+    /// Value centred over a bar. The text inverts what's under it, so it
+    /// reads dark on the filled part and lit on the empty part.
+    fn draw_bar_value(&mut self, pos: &Position, text: &str) {
+        let text_w = |scale: i32| text.len() as i32 * 5 * scale - scale;
+        let scale = if pos.h >= 14 && text_w(2) <= pos.w - 2 { 2 } else { 1 };
+        let x = pos.x + (pos.w - text_w(scale)) / 2;
+        let y = pos.y + (pos.h - 5 * scale) / 2;
+        self.canvas.draw_text_scaled_invert(x, y, text, scale);
+    }
+
+    /// Fills `pos` up to `percent`, inside a `border` px frame (0 for none).
+    /// With `segments > 0` the fill is split into that many blocks with 1px
+    /// gaps, and only whole blocks light up.
+    fn draw_bar(
+        &mut self,
+        pos: &Position,
+        percent: f32,
+        direction: &str,
+        border: i32,
+        segments: usize,
+    ) {
         let p = percent.clamp(0.0, 100.0);
 
-        if border {
-            self.canvas.rect_border(pos.x, pos.y, pos.w, pos.h, true);
-        }
+        self.canvas
+            .rect_border_thick(pos.x, pos.y, pos.w, pos.h, border, true);
 
-        let inner_x = if border { pos.x + 1 } else { pos.x };
-        let inner_y = if border { pos.y + 1 } else { pos.y };
-        let inner_w = if border { pos.w - 2 } else { pos.w };
-        let inner_h = if border { pos.h - 2 } else { pos.h };
+        let inner_x = pos.x + border;
+        let inner_y = pos.y + border;
+        let inner_w = pos.w - 2 * border;
+        let inner_h = pos.h - 2 * border;
 
         if inner_w <= 0 || inner_h <= 0 {
             return;
         }
 
-        if direction == "vertical" {
-            let fill_h = ((inner_h as f32) * (p / 100.0)).round() as i32;
-            let y = inner_y + (inner_h - fill_h);
-            self.canvas.rect_fill(inner_x, y, inner_w, fill_h, true);
+        // `vertical` fills bottom→top and `horizontal-reverse` right→left, so
+        // their spans are measured back from the far edge.
+        let vertical = direction.starts_with("vertical");
+        let from_far_edge = direction == "vertical" || direction == "horizontal-reverse";
+        let length = if vertical { inner_h } else { inner_w };
+
+        // (offset, size) runs along the fill axis, from where the fill starts.
+        let mut spans = Vec::new();
+        let count = (segments as i32).min((length + 1) / 2);
+        if count > 0 {
+            // Block i spans [i * (length + 1) / count, (i + 1) * (length + 1) / count - 1),
+            // so the blocks plus their gaps exactly cover the inner length.
+            let lit = ((count as f32) * (p / 100.0)).round() as i32;
+            for i in 0..lit {
+                let start = i * (length + 1) / count;
+                spans.push((start, (i + 1) * (length + 1) / count - 1 - start));
+            }
         } else {
-            let fill_w = ((inner_w as f32) * (p / 100.0)).round() as i32;
-            self.canvas.rect_fill(inner_x, inner_y, fill_w, inner_h, true);
+            spans.push((0, ((length as f32) * (p / 100.0)).round() as i32));
+        }
+
+        for (offset, size) in spans {
+            let offset = if from_far_edge { length - offset - size } else { offset };
+            if vertical {
+                self.canvas.rect_fill(inner_x, inner_y + offset, inner_w, size, true);
+            } else {
+                self.canvas.rect_fill(inner_x + offset, inner_y, size, inner_h, true);
+            }
         }
     }
 
-    fn draw_graph(&mut self, pos: &Position, history: &VecDeque<f32>) {
+    /// Dotted horizontal lines at `levels` percent of the widget's height,
+    /// drawn before the graph so the data stays on top.
+    fn draw_gridlines(&mut self, pos: &Position, levels: &[f32]) {
+        let bottom = pos.y + pos.h - 1;
+        for &level in levels {
+            let y = bottom - (level / 100.0 * (pos.h - 1) as f32).round() as i32;
+            self.canvas
+                .rect_fill_pattern(pos.x, y, pos.w, 1, Pattern::VLines);
+        }
+    }
+
+    /// Line graph of `history` with the area below it dithered; `full` is the
+    /// value drawn at the top edge.
+    fn draw_graph(&mut self, pos: &Position, history: &VecDeque<f32>, full: f32) {
         if history.len() < 2 || pos.w <= 1 || pos.h <= 1 {
             return;
         }
 
         let len = history.len();
         let bottom = pos.y + pos.h - 1;
-
-        // Collect graph Y for each column via linear interpolation between sample points
-        let mut col_y: Vec<i32> = Vec::with_capacity(pos.w as usize);
-        let mut prev_x = pos.x;
-        let mut prev_vy = pos.y + pos.h - 1 - ((history[0] / 100.0) * (pos.h - 1) as f32) as i32;
-
-        for (i, value) in history.iter().enumerate().take(len).skip(1) {
-            let x = pos.x + ((i as i32) * (pos.w - 1) / (len as i32 - 1));
-            let vy = pos.y + pos.h - 1 - ((*value / 100.0) * (pos.h - 1) as f32) as i32;
-
-            // Interpolate columns between prev_x and x
-            let dx = x - prev_x;
-            for cx in prev_x..=x {
-                let t = if dx == 0 { 0.0 } else { (cx - prev_x) as f32 / dx as f32 };
-                let line_y = prev_vy as f32 + t * (vy - prev_vy) as f32;
-                col_y.push(line_y.round() as i32);
-            }
-
-            prev_x = x + 1; // avoid duplicate column
-            prev_vy = vy;
-        }
+        let sample_y = |value: f32| bottom - ((value / full) * (pos.h - 1) as f32) as i32;
+
+        // Exactly one line Y per column: column c sits at history position
+        // c * (len - 1) / (w - 1), interpolated between the samples around it.
+        let span = (pos.w - 1) as usize;
+        let col_y: Vec<i32> = (0..pos.w as usize)
+            .map(|c| {
+                let at = c * (len - 1);
+                let i = (at / span).min(len - 2);
+                let frac = (at - i * span) as f32 / span as f32;
+                let (y0, y1) = (sample_y(history[i]), sample_y(history[i + 1]));
+                ((y0 as f32 + frac * (y1 - y0) as f32).round() as i32).clamp(pos.y, bottom)
+            })
+            .collect();
 
         // Fill below line with checkerboard dither, then draw the line itself
         for (ci, &ly) in col_y.iter().enumerate() {
             let cx = pos.x + ci as i32;
-            // Dithered fill: from line_y+1 down to bottom
-            for fy in (ly + 1)..=bottom {
-                if (cx + fy) % 2 == 0 {
-                    self.canvas.set(cx, fy, true);
-                }
-            }
+            self.canvas
+                .rect_fill_pattern(cx, ly + 1, 1, bottom - ly, Pattern::Checker);
             // Solid line pixel
             self.canvas.set(cx, ly, true);
         }
     }
 }
 
-fn human_speed(bytes_per_sec: f64) -> String {
-    const UNITS: [char; 4] = ['B', 'K', 'M', 'G'];
+/// Applies `display.rotation` and then `display.mirror` to a finished frame.
+pub fn orient(canvas: &mut Canvas, display: &Display) {
+    if display.rotation == 180 {
+        canvas.rotate_180();
+    }
+    match display.mirror {
+        Mirror::None => {}
+        Mirror::Horizontal => canvas.flip_horizontal(),
+        Mirror::Vertical => canvas.flip_vertical(),
+    }
+}
+
+/// When (0-1) element `seed` drops out of the boot logo's dissolve.
+fn dissolve_noise(seed: usize) -> f32 {
+    (seed.wrapping_mul(37).wrapping_add(17) % 100) as f32 / 100.0
+}
 
-    let mut value = bytes_per_sec.max(0.0);
+/// Local (hour, minute, second) of the wall clock.
+fn local_time() -> (u32, u32, u32) {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::localtime_r(&now, &mut tm);
+    }
+    (tm.tm_hour as u32, tm.tm_min as u32, tm.tm_sec as u32)
+}
+
+/// (down, up) bytes/s for a network widget: its own `interface`, or the default one.
+fn network_rates(widget: &Widget, sample: &MetricsSample) -> (f64, f64) {
+    match &widget.interface {
+        Some(iface) => sample.net_rates.get(iface).copied().unwrap_or((0.0, 0.0)),
+        None => (sample.net_down_bps, sample.net_up_bps),
+    }
+}
+
+/// Whether the widget's metric (its `source`, or the one its type shows) is
+/// above `alert_threshold`.
+fn widget_alerting(widget: &Widget, sample: &MetricsSample) -> bool {
+    let Some(threshold) = widget.alert_threshold else {
+        return false;
+    };
+    let source = match widget.source.as_deref() {
+        Some(source) => source,
+        None if matches!(widget.kind.as_str(), "cpu" | "memory" | "gpu" | "volume") => &widget.kind,
+        None => return false,
+    };
+    metric_value(sample, source) > threshold
+}
+
+/// Percentage for a named metric source; unknown names read as 0.
+fn metric_value(sample: &MetricsSample, source: &str) -> f32 {
+    match source {
+        "cpu" => sample.cpu_percent,
+        "memory" => sample.mem_percent,
+        "gpu" => sample.gpu_percent,
+        "volume" if sample.is_muted => 0.0,
+        "volume" => sample.volume_percent,
+        _ => 0.0,
+    }
+}
+
+/// Frame offset for anti-burn-in shifting: steps once per `period_ms` through
+/// the centre and its eight neighbours, so no pixel stays put for long.
+fn burn_in_offset(elapsed: Duration, period_ms: u32) -> (i32, i32) {
+    const CYCLE: [(i32, i32); 9] = [
+        (0, 0),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+    ];
+    let step = elapsed.as_millis() / period_ms as u128;
+    CYCLE[(step % CYCLE.len() as u128) as usize]
+}
+
+/// Where `value` sits between `min` and `max`, as a clamped 0-100 percentage.
+fn range_percent(value: f32, min: f32, max: f32) -> f32 {
+    if max <= min {
+        return 0.0;
+    }
+    ((value - min) / (max - min) * 100.0).clamp(0.0, 100.0)
+}
+
+/// `42%` on the default 0-100 range, otherwise just the clamped value.
+fn range_label(value: f32, min: f32, max: f32) -> String {
+    let shown = value.clamp(min, max.max(min)).round() as i32;
+    if min == 0.0 && max == 100.0 {
+        format!("{shown}%")
+    } else {
+        format!("{shown}")
+    }
+}
+
+/// Splits a rate into a formatted value and its unit. Bytes step by 1024
+/// (`B`, `K`, `M`, `G`, `T`); bits by 1000 (`b`, `Kb`, `Mb`, `Gb`, `Tb`).
+fn human_speed(bytes_per_sec: f64, bits: bool) -> (String, &'static str) {
+    const BYTE_UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    const BIT_UNITS: [&str; 5] = ["b", "Kb", "Mb", "Gb", "Tb"];
+
+    let (mut value, step, units) = if bits {
+        (bytes_per_sec.max(0.0) * 8.0, 1000.0, BIT_UNITS)
+    } else {
+        (bytes_per_sec.max(0.0), 1024.0, BYTE_UNITS)
+    };
     let mut unit = 0;
-    while value >= 1024.0 && unit < UNITS.len() - 1 {
-        value /= 1024.0;
+    while value >= step && unit < units.len() - 1 {
+        value /= step;
         unit += 1;
     }
 
     if unit == 0 {
-        format!("{:.0}{}", value, UNITS[unit])
+        (format!("{:.0}", value), units[unit])
     } else {
-        format!("{:.1}{}", value, UNITS[unit])
+        (format!("{:.1}", value), units[unit])
     }
 }
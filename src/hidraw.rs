@@ -1,82 +1,467 @@
+use std::ffi::CString;
+use std::fmt;
 use std::fs::{self, File, OpenOptions};
-use std::io::Write;
-use std::path::Path;
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{bail, Result};
 
-const FRAME_BYTES: usize = 640;
-const PACKET_BYTES: usize = 642;
+use crate::log;
+use crate::sink::FrameSink;
+
+const RETRY_BASE: Duration = Duration::from_millis(100);
+const RETRY_MAX: Duration = Duration::from_secs(5);
+// A report that can't be queued within this long means the device stopped
+// draining; the write fails and reconnect backoff takes over.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+// Bounds one `read_input` call so a chatty device can't stall a frame.
+const MAX_INPUT_REPORTS: usize = 16;
+// Larger than any HID report the keyboard sends.
+const INPUT_REPORT_MAX: usize = 256;
+
+/// Why talking to the keyboard failed. `is_fatal` separates bugs in the
+/// caller from device trouble that reconnecting can fix.
+#[derive(Debug)]
+pub enum HidError {
+    /// No hidraw node has this VID/PID (under `sysfs_path`, if set).
+    NotFound { vid: u16, pid: u16, interface: String, sysfs_path: Option<String> },
+    /// A configured `device.path` doesn't exist.
+    PathMissing(PathBuf),
+    /// The node exists but this user may not write it. `ids` (VID, PID) are
+    /// known when it was found by discovery and go into the udev rule hint.
+    PermissionDenied { path: PathBuf, ids: Option<(u16, u16)> },
+    /// Opening the node failed for another reason.
+    OpenFailed { path: PathBuf, source: io::Error },
+    /// `/sys/class/hidraw` couldn't be listed.
+    Discovery(io::Error),
+    /// Writing a report to the open node failed, also after reopening it.
+    WriteFailed(io::Error),
+    /// A frame whose length doesn't match the packet layout.
+    BadFrameSize { got: usize, expected: usize },
+    /// `set_brightness` without `with_brightness_report`.
+    NoBrightnessReport,
+}
+
+impl HidError {
+    /// Errors retrying won't fix: the caller passed something wrong.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, HidError::BadFrameSize { .. } | HidError::NoBrightnessReport)
+    }
+}
+
+impl fmt::Display for HidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HidError::NotFound { vid, pid, interface, sysfs_path } => {
+                write!(
+                    f,
+                    "SteelSeries hidraw device not found (VID {vid:04X}, PID {pid:04X}, \
+                     interface {interface}"
+                )?;
+                match sysfs_path {
+                    Some(sysfs) => write!(f, ", sysfs path containing {sysfs})"),
+                    None => f.write_str(")"),
+                }
+            }
+            HidError::PathMissing(path) => write!(f, "device {} does not exist", path.display()),
+            HidError::PermissionDenied { path, ids: Some((vid, pid)) } => {
+                f.write_str(&permission_hint(path, *vid, *pid))
+            }
+            HidError::PermissionDenied { path, ids: None } => write!(
+                f,
+                "device {} is not writable by this user; install 99-steelseries.rules \
+                 into /etc/udev/rules.d/ (or fix the node's permissions) and replug the keyboard",
+                path.display()
+            ),
+            // The io::Error is the `source`; `{:#}` chains print it after these.
+            HidError::OpenFailed { path, .. } => write!(f, "failed opening {}", path.display()),
+            HidError::Discovery(_) => f.write_str("cannot read /sys/class/hidraw"),
+            HidError::WriteFailed(_) => f.write_str("failed to write packet"),
+            HidError::BadFrameSize { got, expected } => {
+                write!(f, "invalid frame size: got {got}, expected {expected}")
+            }
+            HidError::NoBrightnessReport => f.write_str("no brightness report id configured"),
+        }
+    }
+}
+
+impl std::error::Error for HidError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HidError::OpenFailed { source, .. } => Some(source),
+            HidError::Discovery(err) | HidError::WriteFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Shape of one output report: `report_id`, the packed frame, then zero padding.
+#[derive(Debug, Clone, Copy)]
+pub struct PacketLayout {
+    pub report_id: u8,
+    pub frame_bytes: usize,
+    pub packet_bytes: usize,
+}
+
+impl PacketLayout {
+    /// Apex5: 0x61 + 640-byte 128×40 frame + 1 byte padding.
+    pub const APEX5: PacketLayout = PacketLayout {
+        report_id: 0x61,
+        frame_bytes: 640,
+        packet_bytes: 642,
+    };
+
+    /// Layout for a `width`×`height` mono panel, with the Apex5 convention of
+    /// one padding byte after the frame. An explicit `packet_bytes` must match
+    /// that, so a typo fails here instead of sending oversized reports.
+    pub fn for_display(
+        width: usize,
+        height: usize,
+        report_id: u8,
+        packet_bytes: Option<usize>,
+    ) -> Result<Self> {
+        let frame_bytes = (width * height).div_ceil(8);
+        let expected = frame_bytes + 2;
+        let packet_bytes = packet_bytes.unwrap_or(expected);
+        if frame_bytes == 0 {
+            bail!("display {width}x{height} has no pixels");
+        }
+        if packet_bytes != expected {
+            bail!(
+                "packet length {packet_bytes} doesn't match display {width}x{height}: \
+                 expected 1 report byte + {frame_bytes} frame bytes + 1 padding byte = \
+                 {expected}"
+            );
+        }
+        Ok(Self {
+            report_id,
+            frame_bytes,
+            packet_bytes,
+        })
+    }
+}
 
 pub struct HidSender {
     vid: u16,
     pid: u16,
     interface: String,
+    layout: PacketLayout,
+    device_path: Option<PathBuf>,
+    sysfs_path: Option<String>,
     file: Option<File>,
-    packet: [u8; PACKET_BYTES],
+    packet: Vec<u8>,
+    // Last frame successfully written; only trusted while `last_sent_valid`.
+    last_sent: Vec<u8>,
+    last_sent_valid: bool,
+    skipped_frames: u64,
+    // Reconnect backoff: consecutive failed sends and when to try again.
+    failures: u32,
+    retry_at: Option<Instant>,
+    brightness_report: Option<u8>,
+    read_input: bool,
 }
 
 impl HidSender {
-    pub fn new(vid: u16, pid: u16, interface: String) -> Self {
+    pub fn new(vid: u16, pid: u16, interface: String, layout: PacketLayout) -> Self {
         Self {
             vid,
             pid,
             interface,
+            layout,
+            device_path: None,
+            sysfs_path: None,
             file: None,
-            packet: [0; PACKET_BYTES],
+            packet: vec![0; layout.packet_bytes],
+            last_sent: vec![0; layout.frame_bytes],
+            last_sent_valid: false,
+            skipped_frames: 0,
+            failures: 0,
+            retry_at: None,
+            brightness_report: None,
+            read_input: false,
         }
     }
 
-    pub fn send_frame(&mut self, frame: &[u8]) -> Result<()> {
-        if frame.len() != FRAME_BYTES {
-            bail!("invalid frame size: got {}, expected {}", frame.len(), FRAME_BYTES);
-        }
+    /// Use a fixed hidraw node instead of discovering one by VID/PID.
+    pub fn with_device_path(mut self, path: PathBuf) -> Self {
+        self.device_path = Some(path);
+        self
+    }
 
-        self.ensure_open()?;
+    /// Only discover nodes whose sysfs device path contains `sysfs_path`, to
+    /// tell apart keyboards with the same VID/PID.
+    pub fn with_sysfs_path(mut self, sysfs_path: String) -> Self {
+        self.sysfs_path = Some(sysfs_path);
+        self
+    }
 
-        self.packet.fill(0);
-        self.packet[0] = 0x61;
-        self.packet[1..1 + FRAME_BYTES].copy_from_slice(frame);
+    /// Report id `set_brightness` writes to. Experimental: no brightness report
+    /// is documented for the Apex 5, so there is no default.
+    pub fn with_brightness_report(mut self, report_id: u8) -> Self {
+        self.brightness_report = Some(report_id);
+        self
+    }
 
-        let Some(file) = self.file.as_mut() else {
-            bail!("device file unavailable");
+    /// Make `read_input` return the input reports the device sends.
+    pub fn with_input_reading(mut self) -> Self {
+        self.read_input = true;
+        self
+    }
+
+    /// Experimental: writes `[brightness_report, level]`, zero-padded to the
+    /// frame packet length, as an output report. Firmware that doesn't know the
+    /// report ignores it or may show garbage until the next frame.
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), HidError> {
+        let Some(report_id) = self.brightness_report else {
+            return Err(HidError::NoBrightnessReport);
         };
+        self.packet.fill(0);
+        self.packet[0] = report_id;
+        self.packet[1] = level;
+        self.write_report()?;
+        // Don't trust the panel still shows the last frame.
+        self.last_sent_valid = false;
+        Ok(())
+    }
+
+    fn write_packet(&mut self, frame: &[u8]) -> Result<(), HidError> {
+        self.packet.fill(0);
+        self.packet[0] = self.layout.report_id;
+        self.packet[1..1 + frame.len()].copy_from_slice(frame);
 
-        if let Err(err) = file.write_all(&self.packet) {
-            self.file = None;
-            self.ensure_open()?;
-            let retry = self
-                .file
-                .as_mut()
-                .ok_or_else(|| anyhow!("device reopen failed"))?;
-            retry
-                .write_all(&self.packet)
-                .with_context(|| format!("failed to write packet after reconnect: {err}"))?;
+        match self.write_report() {
+            // One reopen, for a node that went stale (keyboard replugged). A
+            // stuck device goes straight to backoff instead of stalling twice.
+            Err(HidError::WriteFailed(err)) if err.kind() != ErrorKind::TimedOut => {
+                self.file = None;
+                self.write_report()?;
+            }
+            result => result?,
         }
 
+        self.last_sent.copy_from_slice(frame);
+        self.last_sent_valid = true;
         Ok(())
     }
 
-    fn ensure_open(&mut self) -> Result<()> {
+    /// Writes `packet` as is, opening the device first if needed.
+    fn write_report(&mut self) -> Result<(), HidError> {
+        self.ensure_open()?;
+        let file = self.file.as_mut().expect("ensure_open sets the file");
+        write_all_timeout(file, &self.packet, WRITE_TIMEOUT).map_err(HidError::WriteFailed)
+    }
+
+    fn ensure_open(&mut self) -> Result<(), HidError> {
         if self.file.is_some() {
             return Ok(());
         }
 
-        let device_path = discover_hidraw(self.vid, self.pid, &self.interface)?;
-        let file = OpenOptions::new()
+        // A fresh handle means the panel may show anything; always resend.
+        self.last_sent_valid = false;
+
+        let device_path = match &self.device_path {
+            Some(path) => path.clone(),
+            None => PathBuf::from(discover_hidraw(
+                self.vid,
+                self.pid,
+                &self.interface,
+                self.sysfs_path.as_deref(),
+            )?),
+        };
+        log::debug!("opening {}", device_path.display());
+        // Non-blocking, so a device that stops draining can't hang the loop;
+        // `write_all_timeout` waits for it instead.
+        let opened = OpenOptions::new()
             .read(true)
             .write(true)
-            .open(&device_path)
-            .with_context(|| format!("failed opening {}", device_path))?;
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&device_path);
+        let file = match opened {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+                return Err(HidError::PermissionDenied {
+                    path: device_path,
+                    ids: Some((self.vid, self.pid)),
+                });
+            }
+            Err(source) => return Err(HidError::OpenFailed { path: device_path, source }),
+        };
         self.file = Some(file);
         Ok(())
     }
 }
 
-fn discover_hidraw(vid: u16, pid: u16, interface: &str) -> Result<String> {
+impl FrameSink for HidSender {
+    fn send_frame(&mut self, frame: &[u8]) -> Result<()> {
+        let frame_bytes = self.layout.frame_bytes;
+        if frame.len() != frame_bytes {
+            return Err(HidError::BadFrameSize {
+                got: frame.len(),
+                expected: frame_bytes,
+            }
+            .into());
+        }
+
+        // Identical frames are skipped while the device stays open.
+        if self.file.is_some() && self.last_sent_valid && self.last_sent == frame {
+            self.skipped_frames += 1;
+            return Ok(());
+        }
+
+        if let Some(retry_at) = self.retry_at
+            && Instant::now() < retry_at
+        {
+            return Ok(());
+        }
+
+        match self.write_packet(frame) {
+            Ok(()) => {
+                if self.failures > 0 {
+                    log::info!("device reconnected");
+                }
+                self.failures = 0;
+                self.retry_at = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.file = None;
+                self.failures = self.failures.saturating_add(1);
+                let backoff = (RETRY_BASE * 2u32.saturating_pow(self.failures - 1)).min(RETRY_MAX);
+                self.retry_at = Some(Instant::now() + backoff);
+                // Only the first failure of a streak is reported; the rest stay quiet.
+                if self.failures == 1 {
+                    Err(anyhow::Error::new(err).context("device lost, retrying"))
+                } else {
+                    log::debug!("retry {} failed: {err:#}", self.failures - 1);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn skipped_frames(&self) -> u64 {
+        self.skipped_frames
+    }
+
+    /// Polls the open node with a zero timeout and reads whatever reports are
+    /// queued. Nothing is read while the device is closed; reopening is left
+    /// to `send_frame`.
+    fn read_input(&mut self, reports: &mut Vec<Vec<u8>>) {
+        if !self.read_input {
+            return;
+        }
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let mut buf = [0u8; INPUT_REPORT_MAX];
+        for _ in 0..MAX_INPUT_REPORTS {
+            let mut fds = libc::pollfd {
+                fd: file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&mut fds, 1, 0) };
+            if ready <= 0 || fds.revents & libc::POLLIN == 0 {
+                return;
+            }
+            // hidraw hands out one whole report per read.
+            match file.read(&mut buf) {
+                Ok(len) if len > 0 => reports.push(buf[..len].to_vec()),
+                _ => return,
+            }
+        }
+    }
+}
+
+/// `write_all` for a non-blocking fd: on `WouldBlock` it waits for the fd to
+/// become writable, and gives up with `TimedOut` once `timeout` has passed.
+/// Short writes continue with the rest; `Interrupted` is retried.
+fn write_all_timeout(
+    writer: &mut (impl Write + AsRawFd),
+    mut buf: &[u8],
+    timeout: Duration,
+) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    while !buf.is_empty() {
+        match writer.write(buf) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(written) => buf = &buf[written..],
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(io::Error::new(
+                        ErrorKind::TimedOut,
+                        "device stopped accepting reports",
+                    ));
+                }
+                let mut fds = libc::pollfd {
+                    fd: writer.as_raw_fd(),
+                    events: libc::POLLOUT,
+                    revents: 0,
+                };
+                // Ceil so a sub-millisecond remainder still waits once.
+                let wait_ms = left.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32;
+                // EINTR or a timeout both land back at the write.
+                unsafe { libc::poll(&mut fds, 1, wait_ms) };
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that an explicitly configured hidraw node exists and is writable.
+pub fn check_device_path(path: &Path) -> Result<(), HidError> {
+    if !path.exists() {
+        return Err(HidError::PathMissing(path.to_path_buf()));
+    }
+
+    // A path with a NUL byte can't name a device node; `exists` is already false.
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| HidError::PathMissing(path.to_path_buf()))?;
+    let writable = unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } == 0;
+    if !writable {
+        return Err(HidError::PermissionDenied {
+            path: path.to_path_buf(),
+            ids: None,
+        });
+    }
+    Ok(())
+}
+
+fn permission_hint(path: &Path, vid: u16, pid: u16) -> String {
+    let path = path.display();
+    format!(
+        "permission denied opening {path}. Add a udev rule such as\n  \
+         SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{vid:04x}\", ATTRS{{idProduct}}==\"{pid:04x}\", MODE=\"0660\", TAG+=\"uaccess\"\n\
+         to /etc/udev/rules.d/99-steelseries.rules (install.sh does this), or add your user to \
+         the group owning the node (see `ls -l {path}`), then run \
+         `sudo udevadm control --reload-rules && sudo udevadm trigger` and replug the keyboard"
+    )
+}
+
+/// One `/sys/class/hidraw` entry with what discovery reads from sysfs.
+pub struct HidrawNode {
+    pub dev_path: String,
+    /// (VID, PID) from the device's uevent; `None` when it can't be read.
+    pub ids: Option<(u16, u16)>,
+    /// USB interface as `mi_NN`, if the node sits on a USB interface.
+    pub interface: Option<String>,
+    /// Resolved `device` link, e.g. `/sys/devices/pci0000:00/.../1-3:1.1/...`;
+    /// names the USB port the keyboard is plugged into.
+    pub sys_path: Option<String>,
+}
+
+/// Every hidraw node in directory order, including ones without readable IDs.
+pub fn hidraw_nodes() -> Result<Vec<HidrawNode>, HidError> {
     let root = Path::new("/sys/class/hidraw");
-    let entries = fs::read_dir(root).context("cannot read /sys/class/hidraw")?;
-    let mut preferred: Option<String> = None;
-    let mut fallback: Option<String> = None;
+    let entries = fs::read_dir(root).map_err(HidError::Discovery)?;
+    let mut nodes = Vec::new();
 
     for entry in entries.flatten() {
         let name = entry.file_name().to_string_lossy().to_string();
@@ -85,45 +470,66 @@ fn discover_hidraw(vid: u16, pid: u16, interface: &str) -> Result<String> {
         }
 
         let hidraw_sys_path = entry.path();
-        let uevent_path = hidraw_sys_path.join("device/uevent");
-        let uevent = match fs::read_to_string(&uevent_path) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
+        let ids = fs::read_to_string(hidraw_sys_path.join("device/uevent"))
+            .ok()
+            .and_then(|uevent| parse_hid_id(&uevent));
+        let sys_path = fs::canonicalize(hidraw_sys_path.join("device"))
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned());
+        nodes.push(HidrawNode {
+            dev_path: format!("/dev/{name}"),
+            ids,
+            interface: sys_path.as_deref().and_then(interface_from_path),
+            sys_path,
+        });
+    }
+    Ok(nodes)
+}
 
-        let (dev_vid, dev_pid) = match parse_hid_id(&uevent) {
-            Some(v) => v,
-            None => continue,
-        };
+/// Node for `vid:pid`, preferring the one on `interface` and otherwise the
+/// first match. With `sysfs_path`, only nodes whose sysfs device path contains
+/// it are considered.
+pub fn discover_hidraw(
+    vid: u16,
+    pid: u16,
+    interface: &str,
+    sysfs_path: Option<&str>,
+) -> Result<String, HidError> {
+    let mut fallback: Option<String> = None;
 
-        if dev_vid != vid || dev_pid != pid {
+    for node in hidraw_nodes()? {
+        if node.ids != Some((vid, pid)) {
             continue;
         }
-
-        let candidate = format!("/dev/{name}");
-        fallback.get_or_insert_with(|| candidate.clone());
-
-        let iface = interface_from_path(&hidraw_sys_path);
-        if iface.as_deref() == Some(interface) {
-            preferred = Some(candidate);
-            break;
+        if let Some(wanted) = sysfs_path
+            && !node.sys_path.as_deref().is_some_and(|path| path.contains(wanted))
+        {
+            log::debug!("discovery: {} matches VID/PID but not {wanted}", node.dev_path);
+            continue;
         }
-    }
-
-    if let Some(path) = preferred {
-        return Ok(path);
+        if node.interface.as_deref() == Some(interface) {
+            log::debug!("discovery: {} is on {interface}", node.dev_path);
+            return Ok(node.dev_path);
+        }
+        log::debug!(
+            "discovery: {} matches VID/PID but is on {}",
+            node.dev_path,
+            node.interface.as_deref().unwrap_or("no USB interface")
+        );
+        fallback.get_or_insert(node.dev_path);
     }
 
     if let Some(path) = fallback {
+        log::debug!("discovery: no node on {interface}, falling back to {path}");
         return Ok(path);
     }
 
-    bail!(
-        "Apex5 hidraw device not found (VID {:04X}, PID {:04X}, interface {})",
+    Err(HidError::NotFound {
         vid,
         pid,
-        interface
-    )
+        interface: interface.to_string(),
+        sysfs_path: sysfs_path.map(str::to_string),
+    })
 }
 
 fn parse_hid_id(uevent: &str) -> Option<(u16, u16)> {
@@ -142,11 +548,8 @@ fn parse_hid_id(uevent: &str) -> Option<(u16, u16)> {
     None
 }
 
-fn interface_from_path(hidraw_sys_path: &Path) -> Option<String> {
-    let device_link = fs::canonicalize(hidraw_sys_path.join("device")).ok()?;
-    let full = device_link.to_string_lossy();
-
-    for segment in full.split('/') {
+fn interface_from_path(device_path: &str) -> Option<String> {
+    for segment in device_path.split('/') {
         let Some((left, right)) = segment.split_once(':') else {
             continue;
         };
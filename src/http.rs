@@ -0,0 +1,181 @@
+//! A tiny HTTP server on a background thread. `--http <addr>` serves
+//! `/frame.png`, the latest frame of the first keyboard as a PNG, and
+//! `/frame/N.png`, the one of the N-th `--config` (from 0).
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::log;
+use crate::png;
+
+// A client that stalls longer than this is dropped so the next one is served.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+// Request line plus headers; anything longer isn't a browser asking for a frame.
+const MAX_REQUEST: usize = 8192;
+
+struct Frame {
+    packed: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+/// Latest frame of each keyboard. The render loop publishes into it; the
+/// server thread copies a frame out and encodes it outside the lock.
+#[derive(Clone)]
+pub struct FrameShare {
+    frames: Arc<Mutex<Vec<Option<Frame>>>>,
+}
+
+impl FrameShare {
+    pub fn new(count: usize) -> Self {
+        Self {
+            frames: Arc::new(Mutex::new((0..count).map(|_| None).collect())),
+        }
+    }
+
+    pub fn publish(&self, index: usize, packed: &[u8], width: usize, height: usize) {
+        let mut frames = self.frames.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(slot) = frames.get_mut(index) else {
+            return;
+        };
+        match slot {
+            Some(frame) => {
+                frame.packed.clear();
+                frame.packed.extend_from_slice(packed);
+                frame.width = width;
+                frame.height = height;
+            }
+            None => {
+                *slot = Some(Frame {
+                    packed: packed.to_vec(),
+                    width,
+                    height,
+                })
+            }
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.frames.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// `None` until the keyboard's first frame is published.
+    fn png(&self, index: usize, scale: usize) -> Option<Vec<u8>> {
+        let (packed, width, height) = {
+            let frames = self.frames.lock().unwrap_or_else(|e| e.into_inner());
+            let frame = frames.get(index)?.as_ref()?;
+            (frame.packed.clone(), frame.width, frame.height)
+        };
+        Some(png::encode_png(&packed, width, height, scale))
+    }
+}
+
+/// What a handler answers with.
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self { status: "200 OK", content_type, body }
+    }
+
+    pub fn text(status: &'static str, body: &str) -> Self {
+        Self { status, content_type: "text/plain", body: body.as_bytes().to_vec() }
+    }
+}
+
+/// Binds `addr` (e.g. `127.0.0.1:8080`) and answers every GET or HEAD with
+/// `handler(path)` from a background thread, one client at a time. Binding
+/// errors are returned right away.
+pub fn serve<F>(addr: &str, handler: F) -> Result<SocketAddr>
+where
+    F: Fn(&str) -> Response + Send + 'static,
+{
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("failed to listen on {addr}"))?;
+    let local = listener.local_addr()?;
+    thread::Builder::new()
+        .name(format!("http {local}"))
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if let Err(err) = handle(stream, &handler) {
+                            log::debug!("http client: {err:#}");
+                        }
+                    }
+                    Err(err) => log::debug!("http accept failed: {err}"),
+                }
+            }
+        })
+        .context("failed to start the http thread")?;
+    Ok(local)
+}
+
+/// `--http`: serves `frames` as PNGs scaled by `scale`.
+pub fn serve_frames(addr: &str, frames: FrameShare, scale: usize) -> Result<()> {
+    let local = serve(addr, move |path| {
+        let index = match path {
+            "/frame.png" => Some(0),
+            _ => path
+                .strip_prefix("/frame/")
+                .and_then(|rest| rest.strip_suffix(".png"))
+                .and_then(|n| n.parse::<usize>().ok()),
+        }
+        .filter(|&i| i < frames.count());
+        match index.map(|i| frames.png(i, scale)) {
+            Some(Some(body)) => Response::ok("image/png", body),
+            Some(None) => Response::text("503 Service Unavailable", "no frame rendered yet\n"),
+            None => Response::text("404 Not Found", "not found\n"),
+        }
+    })?;
+    log::info!("Serving frames on http://{local}/frame.png");
+    Ok(())
+}
+
+fn handle<F: Fn(&str) -> Response>(mut stream: TcpStream, handler: &F) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut request = Vec::with_capacity(512);
+    let mut buf = [0u8; 512];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    // Browsers add cache-busting queries; handlers only see the path.
+    let path = target.split('?').next().unwrap_or("");
+
+    let response = match method {
+        "GET" | "HEAD" => handler(path),
+        _ => Response::text("405 Method Not Allowed", "GET only\n"),
+    };
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+/// Largest icon accepted from a file, in pixels per side.
+const MAX_ICON_SIZE: usize = 128;
+
+/// Reads a monochrome icon into rows of lit pixels. Accepts PBM (`P1`/`P4`,
+/// black = lit), PGM (`P2`/`P5`, brighter than half of maxval = lit), or a
+/// JSON array of rows, each a string (`#`, `1`, `X` or `x` lit) or an array of
+/// 0/1 or booleans.
+pub fn load_icon(path: &Path) -> Result<Vec<Vec<bool>>> {
+    let bytes = fs::read(path).with_context(|| format!("reading icon {}", path.display()))?;
+    let bitmap = if bytes.starts_with(b"P") {
+        parse_netpbm(&bytes)
+    } else {
+        parse_json_rows(&bytes)
+    }
+    .with_context(|| format!("decoding icon {}", path.display()))?;
+
+    let width = bitmap.first().map_or(0, Vec::len);
+    if width == 0 || bitmap.iter().any(|row| row.len() != width) {
+        bail!(
+            "icon {}: rows must be non-empty and equally long",
+            path.display()
+        );
+    }
+    if width > MAX_ICON_SIZE || bitmap.len() > MAX_ICON_SIZE {
+        bail!(
+            "icon {}: {width}x{} is larger than {MAX_ICON_SIZE}x{MAX_ICON_SIZE}",
+            path.display(),
+            bitmap.len()
+        );
+    }
+    Ok(bitmap)
+}
+
+fn parse_json_rows(bytes: &[u8]) -> Result<Vec<Vec<bool>>> {
+    let rows: Vec<Value> = serde_json::from_slice(bytes)?;
+    rows.iter()
+        .map(|row| match row {
+            Value::String(text) => Ok(text
+                .chars()
+                .map(|c| matches!(c, '#' | '1' | 'X' | 'x'))
+                .collect()),
+            Value::Array(cells) => cells
+                .iter()
+                .map(|cell| match cell {
+                    Value::Bool(on) => Ok(*on),
+                    Value::Number(n) => Ok(n.as_f64().unwrap_or(0.0) != 0.0),
+                    other => bail!("unexpected pixel {other}"),
+                })
+                .collect(),
+            other => bail!("unexpected row {other}"),
+        })
+        .collect()
+}
+
+fn parse_netpbm(bytes: &[u8]) -> Result<Vec<Vec<bool>>> {
+    let mut pos = 0;
+    let magic = next_token(bytes, &mut pos)?;
+    let width = next_number(bytes, &mut pos)?;
+    let height = next_number(bytes, &mut pos)?;
+    if width > MAX_ICON_SIZE || height > MAX_ICON_SIZE {
+        bail!("{width}x{height} is larger than {MAX_ICON_SIZE}x{MAX_ICON_SIZE}");
+    }
+    let max = match magic {
+        "P1" | "P4" => 1,
+        "P2" | "P5" => next_number(bytes, &mut pos)?.max(1),
+        other => bail!("unsupported netpbm type {other}"),
+    };
+
+    let mut rows = vec![vec![false; width]; height];
+    match magic {
+        // One character per pixel; whitespace between them is optional.
+        "P1" => {
+            let mut digits = bytes[pos..].iter().filter(|b| matches!(b, b'0' | b'1'));
+            for row in &mut rows {
+                for px in row.iter_mut() {
+                    *px = *digits.next().context("truncated pixel data")? == b'1';
+                }
+            }
+        }
+        "P2" => {
+            for row in &mut rows {
+                for px in row.iter_mut() {
+                    *px = next_number(bytes, &mut pos)? * 2 > max;
+                }
+            }
+        }
+        // Binary rasters start after a single whitespace byte.
+        "P4" => {
+            let data = bytes.get(pos + 1..).unwrap_or_default();
+            let stride = width.div_ceil(8);
+            if data.len() < stride * height {
+                bail!("truncated pixel data");
+            }
+            for (y, row) in rows.iter_mut().enumerate() {
+                for (x, px) in row.iter_mut().enumerate() {
+                    *px = data[y * stride + x / 8] & (0x80 >> (x % 8)) != 0;
+                }
+            }
+        }
+        _ => {
+            if max > 255 {
+                bail!("16-bit PGM is not supported");
+            }
+            let data = bytes.get(pos + 1..).unwrap_or_default();
+            if data.len() < width * height {
+                bail!("truncated pixel data");
+            }
+            for (y, row) in rows.iter_mut().enumerate() {
+                for (x, px) in row.iter_mut().enumerate() {
+                    *px = data[y * width + x] as usize * 2 > max;
+                }
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Next whitespace-separated header token, skipping `#` comments.
+fn next_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Result<&'a str> {
+    loop {
+        while bytes.get(*pos).is_some_and(u8::is_ascii_whitespace) {
+            *pos += 1;
+        }
+        if bytes.get(*pos) != Some(&b'#') {
+            break;
+        }
+        while bytes.get(*pos).is_some_and(|&b| b != b'\n') {
+            *pos += 1;
+        }
+    }
+    let start = *pos;
+    while bytes.get(*pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    if start == *pos {
+        bail!("truncated header");
+    }
+    Ok(std::str::from_utf8(&bytes[start..*pos])?)
+}
+
+fn next_number(bytes: &[u8], pos: &mut usize) -> Result<usize> {
+    let token = next_token(bytes, pos)?;
+    token
+        .parse()
+        .with_context(|| format!("expected a number, found {token:?}"))
+}
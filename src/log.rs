@@ -0,0 +1,85 @@
+//! Leveled logging to stderr. The level comes from `STAINED_LOG` or `RUST_LOG`
+//! (`off`, `error`, `warn`, `info`, `debug`; default `info`), and `--verbose`
+//! raises it to `debug`.
+
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn prefix(self) -> &'static str {
+        match self {
+            Level::Error => "error: ",
+            Level::Warn => "warning: ",
+            Level::Info => "",
+            Level::Debug => "debug: ",
+        }
+    }
+}
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+/// Sets the level from the environment, then `verbose`. Call once at startup.
+pub fn init(verbose: bool) {
+    let var = env::var("STAINED_LOG").or_else(|_| env::var("RUST_LOG"));
+    let mut max = Level::Info as u8;
+    if let Ok(value) = var {
+        match parse_level(&value) {
+            Some(level) => max = level,
+            None => eprintln!("warning: unknown log level \"{value}\", using info"),
+        }
+    }
+    if verbose {
+        max = max.max(Level::Debug as u8);
+    }
+    MAX_LEVEL.store(max, Ordering::Relaxed);
+}
+
+/// `RUST_LOG`-style value to a max level; `0` is off. Only the last directive
+/// counts, and a `target=` part is ignored since there is a single target.
+fn parse_level(value: &str) -> Option<u8> {
+    let directive = value.rsplit(',').next()?.trim();
+    let level = directive.rsplit('=').next()?.trim();
+    match level.to_ascii_lowercase().as_str() {
+        "off" => Some(0),
+        "error" => Some(Level::Error as u8),
+        "warn" | "warning" => Some(Level::Warn as u8),
+        "info" | "" => Some(Level::Info as u8),
+        // No separate trace output; treat it as the most verbose level.
+        "debug" | "trace" => Some(Level::Debug as u8),
+        _ => None,
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn write(level: Level, args: fmt::Arguments) {
+    if enabled(level) {
+        eprintln!("{}{args}", level.prefix());
+    }
+}
+
+// Named `log_warn` because a bare `warn` clashes with the built-in attribute.
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Warn, format_args!($($arg)*)) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Info, format_args!($($arg)*)) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Debug, format_args!($($arg)*)) };
+}
+
+pub(crate) use {debug, info, log_warn as warn};
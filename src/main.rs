@@ -2,127 +2,1019 @@ mod canvas;
 mod config;
 mod dashboard;
 mod hidraw;
+#[cfg(feature = "http")]
+mod http;
+mod icon;
+mod log;
 mod metrics;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod png;
+mod preview;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod schema;
+mod signals;
+mod sink;
+mod toml;
 mod weather;
 
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 
-use config::DashboardConfig;
+use canvas::Canvas;
+use config::{ConfigWatcher, DashboardConfig, Display, IdleMode};
 use dashboard::DashboardRenderer;
-use hidraw::HidSender;
-use metrics::{MetricIntervals, MetricsCollector};
+use hidraw::{HidError, HidSender, PacketLayout};
+use metrics::{
+    CommandSpec, DeviceBatterySpec, LedOverrides, MetricIntervals, MetricsCollector,
+    MetricsSample, PingSpec,
+};
+use preview::TerminalPreview;
+use sink::{FileSink, FrameSink};
 
 const APEX5_VID: u16 = 0x1038;
 const APEX5_PID: u16 = 0x161C;
 const APEX5_INTERFACE: &str = "mi_01";
+// Written by `--init`; the same layout install.sh copies.
+const DEFAULT_CONFIG: &str = include_str!("../profiles/dashboard.json");
+// Identical frames in a row before the loop starts backing off.
+const IDLE_TICKS_BEFORE_BACKOFF: u32 = 30;
 
 fn main() -> Result<()> {
     let opts = parse_options();
-    let config = DashboardConfig::load(&opts.config_path)
-        .with_context(|| format!("failed to load config from {}", opts.config_path.display()))?;
+    log::init(opts.verbose);
+    if opts.init {
+        return write_default_config(opts.force);
+    }
+    if opts.list_devices {
+        return list_devices(&opts);
+    }
+    if opts.dump_config_schema {
+        println!("{}", serde_json::to_string_pretty(&schema::config_schema())?);
+        return Ok(());
+    }
+    if opts.config_paths.len() > 1
+        && (opts.png_path.is_some()
+            || opts.ascii
+            || opts.preview
+            || opts.output_path.is_some()
+            || opts.device_path.is_some())
+    {
+        bail!(
+            "--png, --ascii, --preview, --output and --device take a single --config; \
+             set device.path in each config instead of --device"
+        );
+    }
+
+    let mut dashboards = Vec::with_capacity(opts.config_paths.len());
+    for path in &opts.config_paths {
+        dashboards.push(Dashboard::open(&opts, path)?);
+    }
+    check_distinct_devices(&opts, &dashboards)?;
+
+    #[cfg(feature = "http")]
+    let frame_share = match &opts.http_addr {
+        Some(addr) => {
+            let share = http::FrameShare::new(dashboards.len());
+            http::serve_frames(addr, share.clone(), opts.png_scale)?;
+            Some(share)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "http"))]
+    if opts.http_addr.is_some() {
+        bail!("--http needs a build with `--features http`");
+    }
+
+    #[cfg(feature = "prometheus")]
+    let metrics_share = match &opts.metrics_addr {
+        Some(addr) => {
+            let share = prometheus::MetricsShare::default();
+            prometheus::serve_metrics(addr, share.clone())?;
+            Some(share)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "prometheus"))]
+    if opts.metrics_addr.is_some() {
+        bail!("--metrics-addr needs a build with `--features prometheus`");
+    }
+
+    // Metrics are machine-wide, so only the first config's broker is used.
+    #[cfg(feature = "mqtt")]
+    let mut mqtt = match &dashboards[0].config.mqtt {
+        Some(config) => Some(mqtt::MqttPublisher::start(config)?),
+        None => None,
+    };
+    #[cfg(not(feature = "mqtt"))]
+    if dashboards[0].config.mqtt.is_some() {
+        log::warn!("the mqtt section needs a build with `--features mqtt`; ignoring it");
+    }
+
+    // One collector for every keyboard, so each metric is read once per tick.
+    let mut metrics = MetricsCollector::with_intervals(MetricIntervals::default());
+    configure_metrics(&mut metrics, &mut dashboards);
+
+    if let Some(path) = &opts.png_path {
+        let dashboard = &mut dashboards[0];
+        let sample = metrics.sample();
+        let frame = dashboard.renderer.render(&dashboard.config, &sample);
+        let display = &dashboard.config.display;
+        png::write_png(path, frame, display.width, display.height, opts.png_scale)?;
+        log::info!("Wrote frame to {}", path.display());
+        return Ok(());
+    }
+
+    if opts.ascii {
+        let dashboard = &mut dashboards[0];
+        let sample = metrics.sample();
+        let frame = dashboard.renderer.render(&dashboard.config, &sample);
+        let display = &dashboard.config.display;
+        print!("{}", preview::frame_to_ascii(frame, display.width, display.height));
+        return Ok(());
+    }
+
+    for dashboard in &dashboards {
+        log::info!(
+            "Running {} from {} at {}ms/frame",
+            if dashboard.config.config_name.is_empty() {
+                "Dashboard"
+            } else {
+                &dashboard.config.config_name
+            },
+            dashboard.config_path.display(),
+            dashboard.tick.as_millis()
+        );
+    }
+
+    signals::install_shutdown_handler();
+    signals::install_profile_handler();
+
+    let mut last_stats = Instant::now();
+    let mut sample = MetricsSample::default();
+    let deadline = opts.duration.map(|limit| Instant::now() + limit);
+
+    loop {
+        let now = Instant::now();
+        metrics.sample_into(&mut sample);
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &mut mqtt {
+            mqtt.publish(&sample);
+        }
+        #[cfg(feature = "prometheus")]
+        if let Some(share) = &metrics_share {
+            share.update(&sample);
+        }
+        for (_index, dashboard) in dashboards
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, d)| d.next_tick <= now)
+        {
+            dashboard.step(&sample)?;
+            #[cfg(feature = "http")]
+            if let Some(share) = &frame_share {
+                let display = &dashboard.config.display;
+                share.publish(_index, &dashboard.last_frame, display.width, display.height);
+            }
+        }
+
+        if opts.one
+            || signals::shutdown_requested()
+            || deadline.is_some_and(|at| Instant::now() >= at)
+        {
+            break;
+        }
+
+        let mut reloaded = false;
+        for dashboard in &mut dashboards {
+            reloaded |= dashboard.reload_if_changed(&opts);
+        }
+        let steps = signals::take_profile_steps();
+        if steps != 0 {
+            let mut switched = false;
+            for dashboard in &mut dashboards {
+                switched |= dashboard.switch_profile(steps);
+            }
+            if !switched && dashboards.iter().all(|d| d.config.profiles.is_empty()) {
+                log::warn!("profile switch requested, but no config has \"profiles\"");
+            }
+            reloaded |= switched;
+        }
+        if reloaded {
+            configure_metrics(&mut metrics, &mut dashboards);
+        }
 
-    let refresh_ms = config.refresh_rate_ms.max(16) as u64;
-    let tick = Duration::from_millis(refresh_ms);
+        if log::enabled(log::Level::Debug) && last_stats.elapsed() >= Duration::from_secs(10) {
+            for dashboard in &dashboards {
+                log::debug!(
+                    "skipped {} unchanged frames so far on {}",
+                    dashboard.sink.skipped_frames(),
+                    dashboard.config_path.display()
+                );
+            }
+            last_stats = Instant::now();
+        }
+
+        let next_tick = dashboards
+            .iter()
+            .map(|d| d.next_tick)
+            .min()
+            .expect("at least one config");
+        // Don't sleep past --duration, even on a long idle interval.
+        let wake = deadline.map_or(next_tick, |at| next_tick.min(at));
+        let now = Instant::now();
+        if now < wake {
+            thread::sleep(wake - now);
+        }
+    }
+
+    // Leave the OLEDs dark rather than frozen on the last frame.
+    if !opts.one && !opts.no_clear {
+        for dashboard in &mut dashboards {
+            let blank = vec![0u8; dashboard.layout.frame_bytes];
+            if let Err(err) = dashboard.sink.send_frame(&blank) {
+                log::warn!("failed to clear display: {err:#}");
+            }
+        }
+    }
+
+    Ok(())
+}
 
-    let mut metrics = MetricsCollector::with_intervals(MetricIntervals {
-        cpu_ms: config.widget_refresh_rate_ms("cpu").unwrap_or(refresh_ms as u32),
-        memory_ms: config
-            .widget_refresh_rate_ms("memory")
-            .unwrap_or(refresh_ms as u32),
+/// Loads and validates the config. Precedence: CLI flags over `STAINED_*`
+/// environment overrides over the file.
+fn load_config(opts: &Options, path: &Path) -> Result<DashboardConfig> {
+    let mut config = DashboardConfig::load(path)?;
+    if !config.unknown_fields.is_empty() {
+        let fields = config.unknown_fields.join(", ");
+        if opts.strict_config {
+            bail!("{}: unknown config fields: {fields}", path.display());
+        }
+        log::warn!("{}: ignoring unknown config fields: {fields}", path.display());
+    }
+    config.apply_env_overrides()?;
+    if let Some(ms) = opts.refresh_ms {
+        config.refresh_rate_ms = ms;
+    }
+    config.validate()?;
+    for warning in config.overlap_warnings() {
+        log::warn!("{warning}");
+    }
+    Ok(config)
+}
+
+/// `--init`: writes the default config to `~/.config/stained-steel/dashboard.json`,
+/// the path the config lookup falls back to.
+fn write_default_config(force: bool) -> Result<()> {
+    let home = env::var_os("HOME").context("HOME is not set, can't locate ~/.config")?;
+    let path = std::path::PathBuf::from(home).join(".config/stained-steel/dashboard.json");
+    if path.exists() && !force {
+        bail!("{} already exists; pass --force to overwrite it", path.display());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    fs::write(&path, DEFAULT_CONFIG).with_context(|| format!("writing {}", path.display()))?;
+    log::info!("Wrote default config to {}", path.display());
+    Ok(())
+}
+
+/// `--list-devices`: prints every hidraw node with its IDs, interface and
+/// sysfs path, and marks the one the (first) config would open.
+fn list_devices(opts: &Options) -> Result<()> {
+    // A broken config shouldn't stop device debugging; fall back to the Apex5 IDs.
+    let config = match load_config(opts, &opts.config_paths[0]) {
+        Ok(config) => Some(config),
+        Err(err) => {
+            log::warn!("{err:#}; matching the Apex5 defaults");
+            None
+        }
+    };
+    let device = config.as_ref().map(|c| &c.device);
+    let vid = device.and_then(|d| d.vendor_id).unwrap_or(APEX5_VID);
+    let pid = device.and_then(|d| d.product_id).unwrap_or(APEX5_PID);
+    let interface = device
+        .and_then(|d| d.interface.clone())
+        .unwrap_or_else(|| APEX5_INTERFACE.to_string());
+    let sysfs_path = device.and_then(|d| d.sysfs_path.as_deref());
+
+    let explicit = opts
+        .device_path
+        .clone()
+        .or_else(|| device.and_then(|d| d.path.clone()));
+    let selected = match &explicit {
+        Some(path) => Some(path.to_string_lossy().into_owned()),
+        None => hidraw::discover_hidraw(vid, pid, &interface, sysfs_path).ok(),
+    };
+
+    let mut nodes = hidraw::hidraw_nodes()?;
+    nodes.sort_by_key(|n| n.dev_path.trim_start_matches("/dev/hidraw").parse::<u32>().ok());
+    if nodes.is_empty() {
+        println!("no hidraw devices found");
+    }
+    for node in &nodes {
+        let ids = match node.ids {
+            Some((v, p)) => format!("{v:04X}:{p:04X}"),
+            None => "????:????".to_string(),
+        };
+        let marker = if selected.as_deref() == Some(node.dev_path.as_str()) {
+            "  <- selected"
+        } else {
+            ""
+        };
+        println!(
+            "{:<14} {ids}  {:<6}{marker}",
+            node.dev_path,
+            node.interface.as_deref().unwrap_or("-")
+        );
+        if let Some(sys_path) = &node.sys_path {
+            println!("    {sys_path}");
+        }
+    }
+
+    match (&explicit, &selected) {
+        (Some(path), _) => println!("configured device path: {}", path.display()),
+        (None, Some(_)) => println!("matching VID {vid:04X}, PID {pid:04X}, interface {interface}"),
+        (None, None) => println!(
+            "no node matches VID {vid:04X}, PID {pid:04X} (interface {interface})"
+        ),
+    }
+    Ok(())
+}
+
+fn frame_interval_ms(config: &DashboardConfig) -> u64 {
+    config.refresh_rate_ms.max(16) as u64
+}
+
+fn metric_intervals(config: &DashboardConfig) -> MetricIntervals {
+    let refresh_ms = frame_interval_ms(config) as u32;
+    MetricIntervals {
+        cpu_ms: config.widget_refresh_rate_ms("cpu").unwrap_or(refresh_ms),
+        memory_ms: config.widget_refresh_rate_ms("memory").unwrap_or(refresh_ms),
         volume_ms: config.widget_refresh_rate_ms("volume").unwrap_or(100),
         audio_ms: config
             .widget_refresh_rate_ms("volume")
-            .unwrap_or(refresh_ms as u32)
+            .unwrap_or(refresh_ms)
             .clamp(12, 40),
         network_ms: config.widget_refresh_rate_ms("network").unwrap_or(1000),
         keyboard_ms: config.widget_refresh_rate_ms("keyboard").unwrap_or(50),
-    });
-    let mut renderer = DashboardRenderer::new(config.display.width, config.display.height);
-    let mut sender = HidSender::new(APEX5_VID, APEX5_PID, APEX5_INTERFACE.to_string());
-
-    eprintln!(
-        "Running {} from {} at {}ms/frame",
-        if config.config_name.is_empty() {
-            "Dashboard"
+        command_ms: 1000,
+        gpu_ms: config
+            .widgets
+            .iter()
+            .any(|w| w.enabled && (w.kind == "gpu" || w.source.as_deref() == Some("gpu")))
+            .then(|| config.widget_refresh_rate_ms("gpu").unwrap_or(1000)),
+        cpu_freq_ms: config
+            .widgets
+            .iter()
+            .any(|w| w.enabled && w.kind == "cpufreq")
+            .then(|| config.widget_refresh_rate_ms("cpufreq").unwrap_or(1000)),
+        fan_ms: config
+            .widgets
+            .iter()
+            .any(|w| w.enabled && w.kind == "fan")
+            .then(|| config.widget_refresh_rate_ms("fan").unwrap_or(1000)),
+        cpu_temp_ms: config
+            .widgets
+            .iter()
+            .any(|w| {
+                w.enabled
+                    && (w.kind == "cputemp"
+                        || (w.kind == "barchart" && w.source.as_deref() == Some("coretemps")))
+            })
+            .then(|| config.widget_refresh_rate_ms("cputemp").unwrap_or(1000)),
+        backlight_ms: config
+            .widgets
+            .iter()
+            .any(|w| w.enabled && w.kind == "backlight")
+            .then(|| config.widget_refresh_rate_ms("backlight").unwrap_or(500)),
+        process_ms: config
+            .widgets
+            .iter()
+            .any(|w| w.enabled && w.kind == "topproc")
+            .then(|| config.widget_refresh_rate_ms("topproc").unwrap_or(2000)),
+        media_ms: config
+            .widgets
+            .iter()
+            .any(|w| w.enabled && w.kind == "media")
+            .then(|| config.widget_refresh_rate_ms("media").unwrap_or(1000)),
+        audio_noise_floor: config.audio.noise_floor,
+        audio_sensitivity: config.audio.sensitivity,
+        audio_smoothing: config.audio.smoothing,
+    }
+}
+
+fn command_specs(config: &DashboardConfig, offset: usize) -> Vec<CommandSpec> {
+    config
+        .widgets
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| w.enabled && w.kind == "command")
+        .filter_map(|(index, w)| {
+            Some(CommandSpec {
+                widget: offset + index,
+                program: w.command.clone()?,
+                args: w.args.clone(),
+                interval_ms: w.refresh_rate_ms,
+            })
+        })
+        .collect()
+}
+
+fn ping_specs(config: &DashboardConfig, offset: usize) -> Vec<PingSpec> {
+    config
+        .widgets
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| w.enabled && w.kind == "ping")
+        .filter_map(|(index, w)| {
+            Some(PingSpec {
+                widget: offset + index,
+                host: w.host.clone()?,
+                interval_ms: w.refresh_rate_ms,
+            })
+        })
+        .collect()
+}
+
+fn device_battery_specs(config: &DashboardConfig, offset: usize) -> Vec<DeviceBatterySpec> {
+    config
+        .widgets
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| w.enabled && w.kind == "devicebattery")
+        .filter_map(|(index, w)| {
+            Some(DeviceBatterySpec {
+                widget: offset + index,
+                device: w.device.clone()?,
+                interval_ms: w.refresh_rate_ms,
+            })
+        })
+        .collect()
+}
+
+fn led_overrides(config: &DashboardConfig) -> LedOverrides {
+    let leds = &config.keyboard_leds;
+    LedOverrides {
+        caps: leds.capslock.clone(),
+        num: leds.numlock.clone(),
+        scroll: leds.scrolllock.clone(),
+        backlight: leds.backlight.clone(),
+    }
+}
+
+// Default `idle_mode: "bounce"` sprite.
+const IDLE_STAR: [&str; 9] = [
+    "....#....",
+    "....#....",
+    "...###...",
+    "..#####..",
+    "#########",
+    "..#####..",
+    "...###...",
+    "....#....",
+    "....#....",
+];
+
+/// Replaces frames once nothing has changed for `after`: with a dark one, or
+/// with `idle_mode: "bounce"` a sprite moving 1px per frame on each axis and
+/// bouncing off the edges.
+struct IdleScreen {
+    after: Duration,
+    last_change: Instant,
+    mode: IdleMode,
+    sprite: Vec<Vec<bool>>,
+    canvas: Canvas,
+    // Frames shown since idling out; places the sprite.
+    idle_frames: u64,
+    frame: Vec<u8>,
+}
+
+impl IdleScreen {
+    fn new(config: &DashboardConfig, frame_bytes: usize) -> Option<Self> {
+        let sprite = config.idle_sprite_bitmap.clone().unwrap_or_else(|| {
+            IDLE_STAR
+                .iter()
+                .map(|row| row.chars().map(|c| c == '#').collect())
+                .collect()
+        });
+        config.idle_blank_ms.map(|ms| Self {
+            after: Duration::from_millis(ms as u64),
+            last_change: Instant::now(),
+            mode: config.idle_mode,
+            sprite,
+            canvas: Canvas::new(config.display.width, config.display.height),
+            idle_frames: 0,
+            frame: vec![0; frame_bytes],
+        })
+    }
+
+    /// The frame to send: `frame` itself unless the display has idled out.
+    /// Any change wakes it on the same tick.
+    fn filter<'a>(&'a mut self, frame: &'a [u8], changed: bool, display: &Display) -> &'a [u8] {
+        if changed {
+            self.last_change = Instant::now();
+            self.idle_frames = 0;
+        }
+        if self.last_change.elapsed() < self.after {
+            return frame;
+        }
+        if self.mode == IdleMode::Bounce {
+            let (w, h) = (display.width as i32, display.height as i32);
+            let (sprite_w, sprite_h) = (self.sprite[0].len() as i32, self.sprite.len() as i32);
+            let x = bounce(self.idle_frames, w - sprite_w);
+            let y = bounce(self.idle_frames, h - sprite_h);
+            self.canvas.clear(false);
+            self.canvas.draw_bitmap(x, y, &self.sprite, false);
+            dashboard::orient(&mut self.canvas, display);
+            self.canvas.pack_into(&mut self.frame);
+            self.idle_frames += 1;
+        }
+        &self.frame
+    }
+}
+
+/// Position after `step` 1px moves back and forth across `0..=range`.
+fn bounce(step: u64, range: i32) -> i32 {
+    if range <= 0 {
+        return 0;
+    }
+    let period = 2 * range as u64;
+    let t = (step % period) as i32;
+    if t <= range { t } else { 2 * range - t }
+}
+
+/// One keyboard: a config with its renderer and sink, paced on its own
+/// `refresh_rate_ms` and idle backoff.
+struct Dashboard {
+    config_path: PathBuf,
+    config: DashboardConfig,
+    layout: PacketLayout,
+    renderer: DashboardRenderer,
+    sink: Box<dyn FrameSink>,
+    watcher: Option<ConfigWatcher>,
+    idle_screen: Option<IdleScreen>,
+    last_frame: Vec<u8>,
+    // Last frame of the previous layout and when `config.transition` started
+    // blending away from it.
+    transition: Option<(Vec<u8>, Instant)>,
+    input_reports: Vec<Vec<u8>>,
+    tick: Duration,
+    interval: Duration,
+    // Identical frames in a row, towards IDLE_TICKS_BEFORE_BACKOFF.
+    idle_ticks: u32,
+    next_tick: Instant,
+}
+
+impl Dashboard {
+    fn open(opts: &Options, config_path: &Path) -> Result<Self> {
+        let config = load_config(opts, config_path)
+            .with_context(|| format!("failed to load config from {}", config_path.display()))?;
+
+        let layout = PacketLayout::for_display(
+            config.display.width,
+            config.display.height,
+            config.device.report_id.unwrap_or(PacketLayout::APEX5.report_id),
+            config.device.packet_len,
+        )
+        .context("invalid display/device configuration")?;
+
+        let mut renderer =
+            DashboardRenderer::new(config.display.width, config.display.height, &config.boot);
+        // Single-frame captures would only ever show the first boot frame.
+        if opts.no_boot || opts.one || opts.png_path.is_some() || opts.ascii {
+            renderer.skip_boot();
+        }
+
+        let sink: Box<dyn FrameSink> = if opts.preview {
+            Box::new(TerminalPreview::new(config.display.width, config.display.height))
+        } else if let Some(path) = &opts.output_path {
+            Box::new(FileSink::create(path)?)
+        } else if opts.png_path.is_some() || opts.ascii {
+            // Captures never send; don't touch the device.
+            Box::new(sink::NullSink)
         } else {
-            &config.config_name
-        },
-        opts.config_path.display(),
-        refresh_ms
-    );
+            Box::new(open_sender(opts, &config, layout)?)
+        };
 
-    let network_iface = config.preferred_network_interface();
-    let mut next_tick = Instant::now();
+        let tick = Duration::from_millis(frame_interval_ms(&config));
+        Ok(Self {
+            config_path: config_path.to_path_buf(),
+            idle_screen: IdleScreen::new(&config, layout.frame_bytes),
+            config,
+            layout,
+            renderer,
+            sink,
+            watcher: opts.watch.then(|| ConfigWatcher::new(config_path)),
+            last_frame: Vec::new(),
+            transition: None,
+            input_reports: Vec::new(),
+            tick,
+            interval: tick,
+            idle_ticks: 0,
+            next_tick: Instant::now(),
+        })
+    }
 
-    loop {
-        if let Err(err) = run_once(
-            &config,
-            &network_iface,
-            &mut metrics,
-            &mut renderer,
-            &mut sender,
-        ) {
-            eprintln!("send failed: {err}");
+    /// Renders and sends one frame from `sample`, then schedules the next.
+    fn step(&mut self, sample: &MetricsSample) -> Result<()> {
+        let changed = match self.send(sample) {
+            Ok(changed) => changed,
+            // Device trouble is retried with backoff by the sender; anything
+            // else would fail the same way every frame.
+            Err(err) if err.downcast_ref::<HidError>().is_some_and(HidError::is_fatal) => {
+                return Err(err);
+            }
+            Err(err) => {
+                log::warn!("send failed: {err:#}");
+                true
+            }
+        };
+
+        // Key presses usually mean a widget (volume, lock keys) is about to
+        // change, so input counts as activity for the idle backoff.
+        self.input_reports.clear();
+        self.sink.read_input(&mut self.input_reports);
+        for report in &self.input_reports {
+            log::debug!("input report: {report:02x?}");
         }
+        let changed = changed || !self.input_reports.is_empty();
 
-        if opts.one {
-            break;
+        // Back off towards idle_refresh_ms while nothing changes; snap back
+        // to the configured rate on the first changed frame.
+        match self.config.idle_refresh_ms {
+            Some(idle_ms) if !changed && !self.renderer.wants_fast_refresh() => {
+                self.idle_ticks = self.idle_ticks.saturating_add(1);
+                if self.idle_ticks >= IDLE_TICKS_BEFORE_BACKOFF {
+                    let idle = Duration::from_millis(idle_ms as u64).max(self.tick);
+                    self.interval = (self.interval * 2).min(idle);
+                }
+            }
+            _ => {
+                self.idle_ticks = 0;
+                self.interval = self.tick;
+            }
         }
 
-        next_tick += tick;
+        self.next_tick += self.interval;
         let now = Instant::now();
-        if now < next_tick {
-            thread::sleep(next_tick - now);
-        } else if now.duration_since(next_tick) > tick {
-            next_tick = now;
+        if now > self.next_tick && now.duration_since(self.next_tick) > self.interval {
+            self.next_tick = now;
         }
+        Ok(())
     }
 
-    Ok(())
+    fn send(&mut self, sample: &MetricsSample) -> Result<bool> {
+        let mut frame = self.renderer.render(&self.config, sample);
+        let mut changed = frame != self.last_frame.as_slice();
+        if changed {
+            self.last_frame.clear();
+            self.last_frame.extend_from_slice(frame);
+        }
+        if let Some((from, started)) = &self.transition
+            && let Some(transition) = self.config.transition
+        {
+            let duration = Duration::from_millis(transition.duration_ms as u64);
+            let progress = started.elapsed().as_secs_f32() / duration.as_secs_f32();
+            if progress < 1.0 {
+                let style = transition.style;
+                frame = self.renderer.transition(from, &self.last_frame, progress, style);
+                changed = true;
+            } else {
+                self.transition = None;
+            }
+        }
+        match self.idle_screen.as_mut() {
+            Some(idle) => self.sink.send_frame(idle.filter(frame, changed, &self.config.display))?,
+            None => self.sink.send_frame(frame)?,
+        }
+        Ok(changed)
+    }
+
+    /// Starts blending from the last frame into the current layout, if
+    /// `config.transition` is set.
+    fn start_transition(&mut self) {
+        self.transition = self
+            .config
+            .transition
+            .filter(|t| t.duration_ms > 0 && !self.last_frame.is_empty())
+            .map(|_| (self.last_frame.clone(), Instant::now()));
+    }
+
+    /// Moves `step` profiles on (SIGUSR1/SIGUSR2). Returns whether the
+    /// widgets changed, so the shared collector can be reconfigured.
+    fn switch_profile(&mut self, step: isize) -> bool {
+        let before = self.config.profile_name().to_string();
+        if !self.config.cycle_profile(step) || self.config.profile_name() == before {
+            return false;
+        }
+        // The previous widgets now wait in `profiles` under their name.
+        self.renderer.switch_widgets(&self.config.profiles[&before], &self.config.widgets);
+        self.start_transition();
+        log::info!(
+            "switched {} to profile {}",
+            self.config_path.display(),
+            self.config.profile_name()
+        );
+        let label = format!("profile \"{}\" ", self.config.profile_name());
+        for warning in config::widget_overlaps(&label, &self.config.widgets) {
+            log::warn!("{warning}");
+        }
+        true
+    }
+
+    /// With `--watch`, swaps in the config once its file changes. Returns
+    /// whether it did, so the shared collector can be reconfigured.
+    fn reload_if_changed(&mut self, opts: &Options) -> bool {
+        if !self.watcher.as_mut().is_some_and(ConfigWatcher::changed) {
+            return false;
+        }
+        match load_config(opts, &self.config_path) {
+            Ok(new_config)
+                if new_config.display.width != self.config.display.width
+                    || new_config.display.height != self.config.display.height =>
+            {
+                log::warn!("config reload ignored: display size changes need a restart");
+                false
+            }
+            Ok(mut new_config) => {
+                // Stay on the same profile if the new config still has it.
+                new_config.select_profile(self.config.profile_name());
+                self.config = new_config;
+                self.start_transition();
+                self.tick = Duration::from_millis(frame_interval_ms(&self.config));
+                self.renderer = DashboardRenderer::new(
+                    self.config.display.width,
+                    self.config.display.height,
+                    &self.config.boot,
+                );
+                self.renderer.skip_boot();
+                self.idle_screen = IdleScreen::new(&self.config, self.layout.frame_bytes);
+                log::info!("reloaded {}", self.config_path.display());
+                true
+            }
+            Err(err) => {
+                log::warn!("config reload failed, keeping previous config: {err:#}");
+                false
+            }
+        }
+    }
 }
 
-fn run_once(
+/// The keyboard sink for `config`, with discovery narrowed by `device.sysfs_path`.
+fn open_sender(
+    opts: &Options,
     config: &DashboardConfig,
-    network_iface: &Option<String>,
-    metrics: &mut MetricsCollector,
-    renderer: &mut DashboardRenderer,
-    sender: &mut HidSender,
-) -> Result<()> {
-    let sample = metrics.sample(network_iface.as_deref());
-    let frame = renderer.render(config, &sample);
-    sender.send_frame(&frame)
+    layout: PacketLayout,
+) -> Result<HidSender> {
+    let vid = config.device.vendor_id.unwrap_or(APEX5_VID);
+    let pid = config.device.product_id.unwrap_or(APEX5_PID);
+    // The Apex5 panel is fixed at 128x40; any other size would fail every
+    // send, so refuse it before the loop starts.
+    if vid == APEX5_VID && pid == APEX5_PID && layout.frame_bytes != PacketLayout::APEX5.frame_bytes
+    {
+        bail!(
+            "display {}x{} packs into {} bytes per frame, but the Apex5 panel takes {} (128x40); \
+             fix display.width/height, or set device.vendor_id/product_id for another keyboard",
+            config.display.width,
+            config.display.height,
+            layout.frame_bytes,
+            PacketLayout::APEX5.frame_bytes
+        );
+    }
+    let sender = HidSender::new(
+        vid,
+        pid,
+        config
+            .device
+            .interface
+            .clone()
+            .unwrap_or_else(|| APEX5_INTERFACE.to_string()),
+        layout,
+    );
+    let device_path = opts.device_path.clone().or_else(|| config.device.path.clone());
+    let mut sender = match device_path {
+        Some(path) => {
+            hidraw::check_device_path(&path)?;
+            sender.with_device_path(path)
+        }
+        None => sender,
+    };
+    if let Some(sysfs_path) = &config.device.sysfs_path {
+        sender = sender.with_sysfs_path(sysfs_path.clone());
+    }
+    if config.device.read_input {
+        sender = sender.with_input_reading();
+    }
+    if let Some(level) = config.display.brightness {
+        match config.device.brightness_report {
+            Some(report_id) => {
+                sender = sender.with_brightness_report(report_id);
+                if let Err(err) = sender.set_brightness(level) {
+                    log::warn!("setting brightness failed: {err:#}");
+                }
+            }
+            None => log::warn!(
+                "display.brightness is experimental and needs device.brightness_report; ignoring it"
+            ),
+        }
+    }
+    Ok(sender)
+}
+
+/// Refuses two configs that would open the same keyboard: the same
+/// `device.path`, or the same IDs with no `device.sysfs_path` telling them apart.
+fn check_distinct_devices(opts: &Options, dashboards: &[Dashboard]) -> Result<()> {
+    if dashboards.len() < 2 || opts.preview || opts.output_path.is_some() {
+        return Ok(());
+    }
+    let selector = |config: &DashboardConfig| match &config.device.path {
+        Some(path) => format!("path {}", path.display()),
+        None => format!(
+            "{:04X}:{:04X} {} {}",
+            config.device.vendor_id.unwrap_or(APEX5_VID),
+            config.device.product_id.unwrap_or(APEX5_PID),
+            config.device.interface.as_deref().unwrap_or(APEX5_INTERFACE),
+            config.device.sysfs_path.as_deref().unwrap_or("")
+        ),
+    };
+    for (i, a) in dashboards.iter().enumerate() {
+        for b in &dashboards[i + 1..] {
+            if selector(&a.config) == selector(&b.config) {
+                bail!(
+                    "{} and {} would both drive the same keyboard; give each its own \
+                     device.sysfs_path or device.path (see --list-devices)",
+                    a.config_path.display(),
+                    b.config_path.display()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Points the shared collector at the widgets of every dashboard. Per-widget
+/// data (commands, pings, device batteries) is keyed by the widget's index
+/// plus the widget count of the configs before it.
+fn configure_metrics(metrics: &mut MetricsCollector, dashboards: &mut [Dashboard]) {
+    let mut intervals: Option<MetricIntervals> = None;
+    let mut commands = Vec::new();
+    let mut pings = Vec::new();
+    let mut batteries = Vec::new();
+    let mut offset = 0;
+    for dashboard in dashboards.iter_mut() {
+        let config = &dashboard.config;
+        let own = metric_intervals(config);
+        intervals = Some(match intervals {
+            Some(merged) => merged.merge(&own),
+            None => own,
+        });
+        commands.extend(command_specs(config, offset));
+        pings.extend(ping_specs(config, offset));
+        batteries.extend(device_battery_specs(config, offset));
+        dashboard.renderer.set_widget_offset(offset);
+        offset += config.widgets.len();
+    }
+    metrics.set_intervals(intervals.unwrap_or_default());
+    metrics.set_commands(commands);
+    metrics.set_pings(pings);
+    metrics.set_device_batteries(batteries);
+    // The lock LEDs and backlight belong to the machine, not a keyboard.
+    if let Some(first) = dashboards.first() {
+        metrics.set_led_overrides(led_overrides(&first.config));
+    }
 }
 
 struct Options {
-    config_path: std::path::PathBuf,
+    /// One per keyboard; `--config` may be repeated.
+    config_paths: Vec<PathBuf>,
     one: bool,
+    preview: bool,
+    png_path: Option<std::path::PathBuf>,
+    png_scale: usize,
+    ascii: bool,
+    output_path: Option<std::path::PathBuf>,
+    verbose: bool,
+    device_path: Option<std::path::PathBuf>,
+    no_clear: bool,
+    no_boot: bool,
+    watch: bool,
+    init: bool,
+    force: bool,
+    list_devices: bool,
+    /// `--dump-config-schema`: print the config's JSON Schema and exit.
+    dump_config_schema: bool,
+    /// `--strict-config`: unknown config keys are an error, not a warning.
+    strict_config: bool,
+    duration: Option<Duration>,
+    /// `--refresh-ms` / `--fps`, overriding `refresh_rate_ms`.
+    refresh_ms: Option<u32>,
+    /// `--http`: address to serve `/frame.png` on (needs the `http` feature).
+    http_addr: Option<String>,
+    /// `--metrics-addr`: address to serve `/metrics` on (needs `prometheus`).
+    metrics_addr: Option<String>,
 }
 
 fn parse_options() -> Options {
-    let mut config_path: Option<std::path::PathBuf> = None;
+    let mut config_paths: Vec<PathBuf> = Vec::new();
     let mut one = false;
+    let mut preview = false;
+    let mut png_path: Option<std::path::PathBuf> = None;
+    let mut png_scale = 4;
+    let mut ascii = false;
+    let mut output_path: Option<std::path::PathBuf> = None;
+    let mut verbose = false;
+    let mut device_path: Option<std::path::PathBuf> = None;
+    let mut no_clear = false;
+    let mut no_boot = false;
+    let mut watch = false;
+    let mut init = false;
+    let mut force = false;
+    let mut list_devices = false;
+    let mut dump_config_schema = false;
+    let mut strict_config = false;
+    let mut duration: Option<Duration> = None;
+    let mut refresh_ms: Option<u32> = None;
+    let mut http_addr: Option<String> = None;
+    let mut metrics_addr: Option<String> = None;
 
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         if arg == "--config" {
             if let Some(path) = args.next() {
-                config_path = Some(path.into());
+                config_paths.push(path.into());
             }
         } else if arg == "--one" {
             one = true;
+        } else if arg == "--preview" {
+            preview = true;
+        } else if arg == "--png" {
+            png_path = args.next().map(Into::into);
+        } else if arg == "--ascii" {
+            ascii = true;
+        } else if arg == "--device" {
+            device_path = args.next().map(Into::into);
+        } else if arg == "--watch" {
+            watch = true;
+        } else if arg == "--init" {
+            init = true;
+        } else if arg == "--force" {
+            force = true;
+        } else if arg == "--list-devices" {
+            list_devices = true;
+        } else if arg == "--dump-config-schema" {
+            dump_config_schema = true;
+        } else if arg == "--strict-config" {
+            strict_config = true;
+        } else if arg == "--no-clear" {
+            no_clear = true;
+        } else if arg == "--no-boot" {
+            no_boot = true;
+        } else if arg == "--verbose" {
+            verbose = true;
+        } else if arg == "--output" {
+            output_path = args.next().map(Into::into);
+        } else if arg == "--duration" {
+            duration = args
+                .next()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|secs| secs.is_finite() && *secs > 0.0)
+                .map(Duration::from_secs_f64);
+        } else if arg == "--refresh-ms" {
+            if let Some(ms) = args.next().and_then(|v| v.parse::<u32>().ok()).filter(|&ms| ms > 0) {
+                refresh_ms = Some(ms);
+            }
+        } else if arg == "--fps" {
+            if let Some(fps) = args
+                .next()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|fps| fps.is_finite() && *fps > 0.0)
+            {
+                refresh_ms = Some((1000.0 / fps).round().max(1.0) as u32);
+            }
+        } else if arg == "--http" {
+            http_addr = args.next();
+        } else if arg == "--metrics-addr" {
+            metrics_addr = args.next();
+        } else if arg == "--png-scale" {
+            png_scale = args
+                .next()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(png_scale)
+                .max(1);
         }
     }
 
-    let config_path = if let Some(path) = config_path {
-        path
-    } else {
+    if config_paths.is_empty() {
         // Priority: local profiles/ → ~/.config/stained-steel/ → Go fallback
         let root_profile = std::path::PathBuf::from("profiles/dashboard.json");
-        if root_profile.exists() {
+        let path = if root_profile.exists() {
             root_profile
         } else if let Some(home) = env::var_os("HOME") {
             let xdg = std::path::PathBuf::from(home).join(".config/stained-steel/dashboard.json");
@@ -133,8 +1025,31 @@ fn parse_options() -> Options {
             }
         } else {
             std::path::PathBuf::from("Go/profiles/dashboard.json")
-        }
-    };
+        };
+        config_paths.push(path);
+    }
 
-    Options { config_path, one }
+    Options {
+        config_paths,
+        one,
+        preview,
+        png_path,
+        png_scale,
+        ascii,
+        output_path,
+        verbose,
+        device_path,
+        no_clear,
+        no_boot,
+        watch,
+        init,
+        force,
+        list_devices,
+        dump_config_schema,
+        strict_config,
+        duration,
+        refresh_ms,
+        http_addr,
+        metrics_addr,
+    }
 }
@@ -1,10 +1,13 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::io::{ErrorKind, Read};
 use std::os::fd::AsRawFd;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::time::{Duration, Instant};
 
+use crate::log;
+
 #[derive(Debug, Clone, Copy)]
 pub struct MetricIntervals {
     pub cpu_ms: u32,
@@ -13,6 +16,56 @@ pub struct MetricIntervals {
     pub audio_ms: u32,
     pub network_ms: u32,
     pub keyboard_ms: u32,
+    /// Default rerun interval for `command` widgets without `refresh_rate_ms`.
+    pub command_ms: u32,
+    /// GPU load polling interval; `None` (nothing shows the GPU) skips it.
+    pub gpu_ms: Option<u32>,
+    /// CPU clock polling interval; `None` (no cpufreq widget) skips it.
+    pub cpu_freq_ms: Option<u32>,
+    /// Fan sensor polling interval; `None` (no fan widget) skips it.
+    pub fan_ms: Option<u32>,
+    /// CPU temperature polling interval; `None` (no cputemp widget) skips it.
+    pub cpu_temp_ms: Option<u32>,
+    /// Keyboard backlight polling interval; `None` (no backlight widget) skips it.
+    pub backlight_ms: Option<u32>,
+    /// Top-process scan interval; `None` (no topproc widget) skips the scan.
+    pub process_ms: Option<u32>,
+    /// playerctl polling interval; `None` (no media widget) never spawns it.
+    pub media_ms: Option<u32>,
+    /// Audio level (0-100) subtracted before smoothing to hide idle hiss.
+    pub audio_noise_floor: f32,
+    /// Multiplier on the monitor RMS before it's scaled to 0-100.
+    pub audio_sensitivity: f32,
+    /// Weight of the previous level in the meter's moving average (0-1).
+    pub audio_smoothing: f32,
+}
+
+impl MetricIntervals {
+    /// The faster of each interval, so one collector can feed several
+    /// dashboards. Audio tuning is kept from `self`.
+    pub fn merge(self, other: &MetricIntervals) -> Self {
+        let fastest = |a: Option<u32>, b: Option<u32>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            cpu_ms: self.cpu_ms.min(other.cpu_ms),
+            memory_ms: self.memory_ms.min(other.memory_ms),
+            volume_ms: self.volume_ms.min(other.volume_ms),
+            audio_ms: self.audio_ms.min(other.audio_ms),
+            network_ms: self.network_ms.min(other.network_ms),
+            keyboard_ms: self.keyboard_ms.min(other.keyboard_ms),
+            command_ms: self.command_ms.min(other.command_ms),
+            gpu_ms: fastest(self.gpu_ms, other.gpu_ms),
+            cpu_freq_ms: fastest(self.cpu_freq_ms, other.cpu_freq_ms),
+            fan_ms: fastest(self.fan_ms, other.fan_ms),
+            cpu_temp_ms: fastest(self.cpu_temp_ms, other.cpu_temp_ms),
+            backlight_ms: fastest(self.backlight_ms, other.backlight_ms),
+            process_ms: fastest(self.process_ms, other.process_ms),
+            media_ms: fastest(self.media_ms, other.media_ms),
+            ..self
+        }
+    }
 }
 
 impl Default for MetricIntervals {
@@ -24,23 +77,72 @@ impl Default for MetricIntervals {
             audio_ms: 25,
             network_ms: 1000,
             keyboard_ms: 50,
+            command_ms: 1000,
+            gpu_ms: None,
+            cpu_freq_ms: None,
+            fan_ms: None,
+            cpu_temp_ms: None,
+            backlight_ms: None,
+            process_ms: None,
+            media_ms: None,
+            audio_noise_floor: 1.4,
+            audio_sensitivity: 1.0,
+            audio_smoothing: 0.80,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MetricsSample {
     pub cpu_percent: f32,
+    /// Busy percent of each logical CPU, in /proc/stat order.
+    pub cpu_core_percents: Vec<f32>,
     pub mem_percent: f32,
+    /// Used (total minus available) and total RAM in GiB.
+    pub mem_used_gb: f32,
+    pub mem_total_gb: f32,
+    /// GPU busy percent; 0 when no supported GPU is present.
+    pub gpu_percent: f32,
+    /// Average current clock over all cores; 0 when it can't be read.
+    pub cpu_freq_mhz: f32,
+    /// First hwmon fan's speed; 0 when there is no fan sensor.
+    pub fan_rpm: u32,
+    /// CPU package temperature in °C; 0 without a coretemp/k10temp sensor.
+    pub cpu_temp: f32,
+    /// Per-core (Intel) or per-die (AMD) temperatures in °C, in sensor order.
+    /// Empty when the sensor only reports the package.
+    pub core_temps: Vec<f32>,
+    /// Keyboard backlight brightness (0-100); `None` without a backlight LED,
+    /// so an unlit backlight still reads as `Some(0.0)`.
+    pub kbd_backlight: Option<f32>,
+    /// Sink volume, kept while muted so the level stays visible; see `is_muted`.
     pub volume_percent: f32,
     pub is_muted: bool,
     pub audio_level: f32,
     pub audio_waveform: Vec<f32>,
+    /// Rates of the default interface, used by network widgets without `interface`.
     pub net_up_bps: f64,
     pub net_down_bps: f64,
+    /// (down, up) bytes/s of every non-loopback interface, keyed by name.
+    pub net_rates: HashMap<String, (f64, f64)>,
     pub caps_lock: bool,
     pub num_lock: bool,
     pub scroll_lock: bool,
+    /// Name and share of total CPU (0-100) of the busiest process.
+    pub top_process: (String, f32),
+    /// "Artist - Title" of the active MPRIS player, empty when none is playing.
+    pub media_title: String,
+    /// Playback position and track length in seconds; length 0 = unknown (streams).
+    pub media_position: f64,
+    pub media_length: f64,
+    /// Latest trimmed stdout of each `command` widget, keyed by widget index.
+    pub command_output: HashMap<usize, String>,
+    /// Recent round-trip times in ms of each `ping` widget, oldest first and
+    /// keyed by widget index. `None` is a ping that timed out or failed.
+    pub ping_rtts: HashMap<usize, VecDeque<Option<f32>>>,
+    /// Charge (0-100) of each `devicebattery` widget's device, keyed by widget
+    /// index; absent while the device isn't known to UPower.
+    pub device_batteries: HashMap<usize, f32>,
 }
 
 #[derive(Default)]
@@ -49,12 +151,194 @@ struct CpuSnapshot {
     idle: u64,
 }
 
-#[derive(Default)]
+impl CpuSnapshot {
+    /// A `cpu`/`cpuN` line of /proc/stat; idle includes iowait.
+    fn parse(line: &str) -> Option<Self> {
+        let parts: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|p| p.parse::<u64>().ok())
+            .collect();
+        if parts.len() < 4 {
+            return None;
+        }
+        Some(Self {
+            total: parts.iter().sum(),
+            idle: parts[3] + parts.get(4).copied().unwrap_or(0),
+        })
+    }
+
+    /// Busy share (0-100) of the time between `last` and this snapshot.
+    fn busy_since(&self, last: &CpuSnapshot) -> f32 {
+        let delta_total = self.total.saturating_sub(last.total) as f32;
+        let delta_idle = self.idle.saturating_sub(last.idle) as f32;
+        if delta_total <= 0.0 {
+            0.0
+        } else {
+            ((delta_total - delta_idle) / delta_total * 100.0).clamp(0.0, 100.0)
+        }
+    }
+}
+
 struct NetSnapshot {
-    iface: String,
     rx: u64,
     tx: u64,
-    at: Option<Instant>,
+    at: Instant,
+}
+
+/// A `/proc` file read at most once per tick, into a buffer kept across ticks.
+struct ProcFile {
+    path: &'static str,
+    text: String,
+    // Unset until first asked for this tick, then whether the read worked.
+    loaded: Option<bool>,
+}
+
+impl ProcFile {
+    fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            text: String::new(),
+            loaded: None,
+        }
+    }
+
+    fn read(&mut self) -> Option<&str> {
+        let loaded = *self.loaded.get_or_insert_with(|| {
+            self.text.clear();
+            fs::File::open(self.path)
+                .and_then(|mut file| file.read_to_string(&mut self.text))
+                .is_ok()
+        });
+        loaded.then_some(self.text.as_str())
+    }
+}
+
+/// The `/proc` files several readers parse. Each is read lazily, so a reader
+/// still inside its interval costs nothing, and shared between readers.
+struct ProcFiles {
+    stat: ProcFile,
+    meminfo: ProcFile,
+    net_dev: ProcFile,
+}
+
+impl ProcFiles {
+    fn new() -> Self {
+        Self {
+            stat: ProcFile::new("/proc/stat"),
+            meminfo: ProcFile::new("/proc/meminfo"),
+            net_dev: ProcFile::new("/proc/net/dev"),
+        }
+    }
+
+    /// Starts a new tick: the next `read` of each file hits the kernel again.
+    fn invalidate(&mut self) {
+        for file in [&mut self.stat, &mut self.meminfo, &mut self.net_dev] {
+            file.loaded = None;
+        }
+    }
+}
+
+/// Program run by a `command` widget, keyed by its index in `config.widgets`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSpec {
+    pub widget: usize,
+    pub program: String,
+    pub args: Vec<String>,
+    pub interval_ms: Option<u32>,
+}
+
+/// Host pinged by a `ping` widget, keyed by its index in `config.widgets`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingSpec {
+    pub widget: usize,
+    pub host: String,
+    pub interval_ms: Option<u32>,
+}
+
+/// UPower device shown by a `devicebattery` widget, keyed by widget index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceBatterySpec {
+    pub widget: usize,
+    /// Matched case-insensitively against the model, native path and object path.
+    pub device: String,
+    pub interval_ms: Option<u32>,
+}
+
+/// Exact `/sys/class/leds` directories for the lock LEDs; unset ones are
+/// found by name. Relative names are looked up under `/sys/class/leds`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LedOverrides {
+    pub caps: Option<PathBuf>,
+    pub num: Option<PathBuf>,
+    pub scroll: Option<PathBuf>,
+    pub backlight: Option<PathBuf>,
+}
+
+struct CommandState {
+    spec: CommandSpec,
+    running: Option<(Child, Instant)>,
+    last_run: Option<Instant>,
+    output: String,
+}
+
+struct PingState {
+    spec: PingSpec,
+    running: Option<(Child, Instant)>,
+    last_run: Option<Instant>,
+    rtts: VecDeque<Option<f32>>,
+}
+
+impl PingState {
+    fn record(&mut self, rtt: Option<f32>) {
+        if self.rtts.len() == PING_HISTORY {
+            self.rtts.pop_front();
+        }
+        self.rtts.push_back(rtt);
+    }
+}
+
+struct DeviceBatteryState {
+    spec: DeviceBatterySpec,
+    running: Option<(Child, Instant)>,
+    last_run: Option<Instant>,
+    percent: Option<f32>,
+}
+
+// `sink_name` of a pw-record capture, which tracks the default sink itself.
+#[cfg(feature = "pipewire")]
+const PIPEWIRE_DEFAULT_SINK: &str = "@DEFAULT_SINK@";
+
+// A command still running after this long is killed and shows nothing.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+// nvidia-smi still running after this long is killed and reads as 0%.
+const NVIDIA_SMI_TIMEOUT: Duration = Duration::from_secs(5);
+// A playerctl call that hangs on D-Bus this long is killed; nothing plays.
+const PLAYERCTL_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Default pause between pings, and how long one may take before it counts
+// as a timeout. Enough round trips are kept to fill the widest display.
+const PING_INTERVAL_MS: u32 = 2000;
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+const PING_HISTORY: usize = 256;
+
+// Peripheral batteries drain slowly; `upower -d` every minute is plenty.
+const DEVICE_BATTERY_INTERVAL_MS: u32 = 60_000;
+
+// How often to look for keyboard LEDs again while none are found.
+const LED_RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct CpuTemps {
+    package: f32,
+    cores: Vec<f32>,
+}
+
+#[derive(Default)]
+struct MediaInfo {
+    title: String,
+    position: f64,
+    length: f64,
 }
 
 struct AudioMonitorCapture {
@@ -66,13 +350,38 @@ pub struct MetricsCollector {
     intervals: MetricIntervals,
     last_cpu_percent: Option<(f32, Instant)>,
     last_mem_percent: Option<(f32, Instant)>,
+    // (used, total) GiB from the same meminfo read as `last_mem_percent`.
+    mem_gb: (f32, f32),
+    last_gpu_percent: Option<(f32, Instant)>,
+    // nvidia-smi poll in flight, and whether spawning it found no binary.
+    nvidia_smi: Option<(Child, Instant)>,
+    nvidia_smi_missing: bool,
+    last_cpu_freq: Option<(f32, Instant)>,
+    last_fan_rpm: Option<(u32, Instant)>,
+    last_cpu_temps: Option<(CpuTemps, Instant)>,
+    last_kbd_backlight: Option<(Option<f32>, Instant)>,
     last_cpu: Option<CpuSnapshot>,
-    last_net: Option<NetSnapshot>,
+    last_cpu_cores: Vec<CpuSnapshot>,
+    cpu_core_percents: Vec<f32>,
+    proc_files: ProcFiles,
+    // Per-interface counters from the previous /proc/net/dev read.
+    last_net: HashMap<String, NetSnapshot>,
     last_network_speed: Option<((f64, f64), Instant)>,
+    net_rates: HashMap<String, (f64, f64)>,
     last_volume: Option<((f32, bool), Instant)>,  // (volume, is_muted)
+    last_media: Option<(MediaInfo, Instant)>,
+    playerctl: Option<(Child, Instant)>,
+    // utime+stime per pid from the previous scan, and when it ran.
+    last_proc_ticks: HashMap<u32, u64>,
+    last_top_process: Option<((String, f32), Instant)>,
     last_audio_level: Option<(f32, Instant)>,
     audio_level_ema: f32,
     audio_monitor: Option<AudioMonitorCapture>,
+    // pw-record start time, to notice it dying straight away (no PipeWire).
+    #[cfg(feature = "pipewire")]
+    pipewire_started: Option<Instant>,
+    #[cfg(feature = "pipewire")]
+    pipewire_unavailable: bool,
     cached_default_sink: Option<String>,
     cached_monitor_source: Option<String>,
     last_audio_route_probe: Option<Instant>,
@@ -83,7 +392,12 @@ pub struct MetricsCollector {
     num_led_path: Option<PathBuf>,
     scroll_led_path: Option<PathBuf>,
     led_paths_resolved: bool,
+    led_paths_resolved_at: Instant,
+    led_overrides: LedOverrides,
     last_audio_waveform: Vec<f32>,
+    commands: Vec<CommandState>,
+    pings: Vec<PingState>,
+    batteries: Vec<DeviceBatteryState>,
 }
 
 impl MetricsCollector {
@@ -92,13 +406,33 @@ impl MetricsCollector {
             intervals,
             last_cpu_percent: None,
             last_mem_percent: None,
+            mem_gb: (0.0, 0.0),
+            last_gpu_percent: None,
+            nvidia_smi: None,
+            nvidia_smi_missing: false,
+            last_cpu_freq: None,
+            last_fan_rpm: None,
+            last_cpu_temps: None,
+            last_kbd_backlight: None,
             last_cpu: None,
-            last_net: None,
+            last_cpu_cores: Vec::new(),
+            cpu_core_percents: Vec::new(),
+            proc_files: ProcFiles::new(),
+            last_net: HashMap::new(),
             last_network_speed: None,
+            net_rates: HashMap::new(),
             last_volume: None,
+            last_media: None,
+            playerctl: None,
+            last_proc_ticks: HashMap::new(),
+            last_top_process: None,
             last_audio_level: None,
             audio_level_ema: 0.0,
             audio_monitor: None,
+            #[cfg(feature = "pipewire")]
+            pipewire_started: None,
+            #[cfg(feature = "pipewire")]
+            pipewire_unavailable: false,
             cached_default_sink: None,
             cached_monitor_source: None,
             last_audio_route_probe: None,
@@ -109,32 +443,173 @@ impl MetricsCollector {
             num_led_path: None,
             scroll_led_path: None,
             led_paths_resolved: false,
+            led_paths_resolved_at: Instant::now(),
+            led_overrides: LedOverrides::default(),
             last_audio_waveform: Vec::with_capacity(128),
+            commands: Vec::new(),
+            pings: Vec::new(),
+            batteries: Vec::new(),
+        }
+    }
+
+    /// Swap in new sampling intervals (e.g. after a config reload) without
+    /// losing CPU/network baselines or the running audio monitor.
+    pub fn set_intervals(&mut self, intervals: MetricIntervals) {
+        self.intervals = intervals;
+    }
+
+    /// Pin lock LEDs to specific sysfs directories; takes effect on the next sample.
+    pub fn set_led_overrides(&mut self, overrides: LedOverrides) {
+        if overrides != self.led_overrides {
+            self.led_overrides = overrides;
+            self.led_paths_resolved = false;
+        }
+    }
+
+    /// Replace the set of `command` widgets to run. Commands whose spec is
+    /// unchanged keep their last output; the rest are killed and restarted.
+    pub fn set_commands(&mut self, specs: Vec<CommandSpec>) {
+        let mut previous = std::mem::take(&mut self.commands);
+        for spec in specs {
+            match previous.iter().position(|state| state.spec == spec) {
+                Some(i) => self.commands.push(previous.swap_remove(i)),
+                None => self.commands.push(CommandState {
+                    spec,
+                    running: None,
+                    last_run: None,
+                    output: String::new(),
+                }),
+            }
+        }
+        for state in &mut previous {
+            stop_child(&mut state.running);
+        }
+    }
+
+    /// Replace the set of `ping` widgets. Hosts whose spec is unchanged keep
+    /// their history; the rest start over.
+    pub fn set_pings(&mut self, specs: Vec<PingSpec>) {
+        let mut previous = std::mem::take(&mut self.pings);
+        for spec in specs {
+            match previous.iter().position(|state| state.spec == spec) {
+                Some(i) => self.pings.push(previous.swap_remove(i)),
+                None => self.pings.push(PingState {
+                    spec,
+                    running: None,
+                    last_run: None,
+                    rtts: VecDeque::with_capacity(PING_HISTORY),
+                }),
+            }
+        }
+        for state in &mut previous {
+            stop_child(&mut state.running);
         }
     }
 
-    pub fn sample(&mut self, preferred_iface: Option<&str>) -> MetricsSample {
+    /// Replace the set of `devicebattery` widgets; unchanged ones keep their
+    /// last reading.
+    pub fn set_device_batteries(&mut self, specs: Vec<DeviceBatterySpec>) {
+        let mut previous = std::mem::take(&mut self.batteries);
+        for spec in specs {
+            match previous.iter().position(|state| state.spec == spec) {
+                Some(i) => self.batteries.push(previous.swap_remove(i)),
+                None => self.batteries.push(DeviceBatteryState {
+                    spec,
+                    running: None,
+                    last_run: None,
+                    percent: None,
+                }),
+            }
+        }
+        for state in &mut previous {
+            stop_child(&mut state.running);
+        }
+    }
+
+    pub fn sample(&mut self) -> MetricsSample {
+        let mut sample = MetricsSample::default();
+        self.sample_into(&mut sample);
+        sample
+    }
+
+    /// Refreshes `out` in place so its waveform buffer is reused every tick.
+    pub fn sample_into(&mut self, out: &mut MetricsSample) {
+        self.proc_files.invalidate();
         let cpu_percent = self.read_cpu_percent();
         let mem_percent = self.read_mem_percent();
-        let (raw_volume, is_muted) = self.read_volume_and_mute();
-        let volume_percent = if is_muted { 0.0 } else { raw_volume };
+        let gpu_percent = self.read_gpu_percent();
+        let cpu_freq_mhz = self.read_cpu_freq_mhz();
+        let fan_rpm = self.read_fan_rpm();
+        self.read_cpu_temps();
+        let kbd_backlight = self.read_kbd_backlight();
+        let (volume_percent, is_muted) = self.read_volume_and_mute();
         let audio_level = self.read_audio_output_level();
-        let (net_down_bps, net_up_bps) = self.read_network_speed(preferred_iface);
+        let (net_down_bps, net_up_bps) = self.read_network_speed();
         let (caps_lock, num_lock, scroll_lock) = self.read_keyboard_leds();
-
-        MetricsSample {
-            cpu_percent,
-            mem_percent,
-            volume_percent,
-            is_muted,
-            audio_level,
-            audio_waveform: self.last_audio_waveform.clone(),
-            net_up_bps,
-            net_down_bps,
-            caps_lock,
-            num_lock,
-            scroll_lock,
+        self.read_command_output();
+        self.read_pings();
+        self.read_device_batteries();
+        self.read_media();
+        self.read_top_process();
+
+        out.command_output.clear();
+        for state in &self.commands {
+            out.command_output
+                .insert(state.spec.widget, state.output.clone());
+        }
+        out.ping_rtts.clear();
+        for state in &self.pings {
+            out.ping_rtts.insert(state.spec.widget, state.rtts.clone());
+        }
+        out.device_batteries.clear();
+        for state in &self.batteries {
+            if let Some(percent) = state.percent {
+                out.device_batteries.insert(state.spec.widget, percent);
+            }
         }
+        match &self.last_top_process {
+            Some(((name, percent), _)) => {
+                out.top_process.0.clone_from(name);
+                out.top_process.1 = *percent;
+            }
+            None => {
+                out.top_process.0.clear();
+                out.top_process.1 = 0.0;
+            }
+        }
+        out.media_title.clear();
+        out.media_position = 0.0;
+        out.media_length = 0.0;
+        if let Some((media, _)) = &self.last_media {
+            out.media_title.push_str(&media.title);
+            out.media_position = media.position;
+            out.media_length = media.length;
+        }
+        out.audio_waveform.clear();
+        out.audio_waveform.extend_from_slice(&self.last_audio_waveform);
+        out.cpu_percent = cpu_percent;
+        out.cpu_core_percents.clone_from(&self.cpu_core_percents);
+        out.mem_percent = mem_percent;
+        (out.mem_used_gb, out.mem_total_gb) = self.mem_gb;
+        out.gpu_percent = gpu_percent;
+        out.cpu_freq_mhz = cpu_freq_mhz;
+        out.fan_rpm = fan_rpm;
+        out.kbd_backlight = kbd_backlight;
+        out.core_temps.clear();
+        out.cpu_temp = 0.0;
+        if let Some((temps, _)) = &self.last_cpu_temps {
+            out.cpu_temp = temps.package;
+            out.core_temps.extend_from_slice(&temps.cores);
+        }
+        out.volume_percent = volume_percent;
+        out.is_muted = is_muted;
+        out.audio_level = audio_level;
+        out.net_up_bps = net_up_bps;
+        out.net_down_bps = net_down_bps;
+        out.net_rates.clone_from(&self.net_rates);
+        out.caps_lock = caps_lock;
+        out.num_lock = num_lock;
+        out.scroll_lock = scroll_lock;
     }
 
     fn read_audio_output_level(&mut self) -> f32 {
@@ -147,10 +622,10 @@ impl MetricsCollector {
         }
 
         let raw = self.read_output_monitor_level().unwrap_or(0.0);
-        let noise_floor = 1.4f32;
-        let trimmed = (raw - noise_floor).max(0.0);
+        let trimmed = (raw - self.intervals.audio_noise_floor).max(0.0);
 
-        self.audio_level_ema = self.audio_level_ema * 0.80 + trimmed * 0.20;
+        let smoothing = self.intervals.audio_smoothing.clamp(0.0, 0.99);
+        self.audio_level_ema = self.audio_level_ema * smoothing + trimmed * (1.0 - smoothing);
         let filtered = if self.audio_level_ema < 0.7 {
             0.0
         } else {
@@ -171,41 +646,28 @@ impl MetricsCollector {
             return cached;
         }
 
-        let content = match fs::read_to_string("/proc/stat") {
-            Ok(v) => v,
-            Err(_) => return 0.0,
+        let Some(content) = self.proc_files.stat.read() else {
+            return 0.0;
         };
 
-        let Some(line) = content.lines().next() else {
+        let mut lines = content.lines();
+        let Some(current) = lines.next().and_then(CpuSnapshot::parse) else {
             return 0.0;
         };
 
-        let parts: Vec<u64> = line
-            .split_whitespace()
-            .skip(1)
-            .filter_map(|p| p.parse::<u64>().ok())
+        // The `cpuN` lines follow the aggregate one.
+        let cores: Vec<CpuSnapshot> = lines
+            .take_while(|line| line.starts_with("cpu"))
+            .filter_map(CpuSnapshot::parse)
             .collect();
-
-        if parts.len() < 4 {
-            return 0.0;
+        self.cpu_core_percents.clear();
+        for (i, core) in cores.iter().enumerate() {
+            let percent = self.last_cpu_cores.get(i).map_or(0.0, |last| core.busy_since(last));
+            self.cpu_core_percents.push(percent);
         }
+        self.last_cpu_cores = cores;
 
-        let idle = parts[3] + parts.get(4).copied().unwrap_or(0);
-        let total: u64 = parts.iter().sum();
-        let current = CpuSnapshot { total, idle };
-
-        let percent = if let Some(last) = &self.last_cpu {
-            let delta_total = current.total.saturating_sub(last.total) as f32;
-            let delta_idle = current.idle.saturating_sub(last.idle) as f32;
-            if delta_total <= 0.0 {
-                0.0
-            } else {
-                ((delta_total - delta_idle) / delta_total) * 100.0
-            }
-        } else {
-            0.0
-        };
-
+        let percent = self.last_cpu.as_ref().map_or(0.0, |last| current.busy_since(last));
         self.last_cpu = Some(current);
         let value = percent.clamp(0.0, 100.0);
         self.last_cpu_percent = Some((value, Instant::now()));
@@ -221,9 +683,8 @@ impl MetricsCollector {
             return cached;
         }
 
-        let content = match fs::read_to_string("/proc/meminfo") {
-            Ok(v) => v,
-            Err(_) => return 0.0,
+        let Some(content) = self.proc_files.meminfo.read() else {
+            return 0.0;
         };
 
         let mut total_kib = 0.0;
@@ -241,11 +702,152 @@ impl MetricsCollector {
             return 0.0;
         }
 
+        const KIB_PER_GIB: f32 = 1024.0 * 1024.0;
+        self.mem_gb = (
+            (total_kib - avail_kib).max(0.0) / KIB_PER_GIB,
+            total_kib / KIB_PER_GIB,
+        );
         let value = ((total_kib - avail_kib) / total_kib * 100.0).clamp(0.0, 100.0);
         self.last_mem_percent = Some((value, Instant::now()));
         value
     }
 
+    /// AMD/Intel expose `gpu_busy_percent` in sysfs; NVIDIA needs nvidia-smi,
+    /// which can take hundreds of ms, so it runs as a child and is collected
+    /// on a later tick like `read_pings`. Until then the last value is shown.
+    fn read_gpu_percent(&mut self) -> f32 {
+        let Some(interval_ms) = self.intervals.gpu_ms else {
+            stop_child(&mut self.nvidia_smi);
+            return 0.0;
+        };
+        let cached = self.last_gpu_percent.map_or(0.0, |(percent, _)| percent);
+        if self.nvidia_smi.is_some() {
+            if let Some(text) = collect_child(&mut self.nvidia_smi, NVIDIA_SMI_TIMEOUT) {
+                let percent = parse_nvidia_smi(&text).unwrap_or(0.0).clamp(0.0, 100.0);
+                self.last_gpu_percent = Some((percent, Instant::now()));
+                return percent;
+            }
+            return cached;
+        }
+        if self
+            .last_gpu_percent
+            .is_some_and(|(_, at)| at.elapsed() < Duration::from_millis(interval_ms as u64))
+        {
+            return cached;
+        }
+
+        if let Some(percent) = read_gpu_busy_sysfs() {
+            let percent = percent.clamp(0.0, 100.0);
+            self.last_gpu_percent = Some((percent, Instant::now()));
+            return percent;
+        }
+        if !self.nvidia_smi_missing {
+            match Command::new("nvidia-smi")
+                .args(["--query-gpu=utilization.gpu", "--format=csv,noheader,nounits"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => {
+                    self.nvidia_smi = Some((child, Instant::now()));
+                    return cached;
+                }
+                // No NVIDIA tools installed: don't spawn again every poll.
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    self.nvidia_smi_missing = true;
+                }
+                Err(_) => {}
+            }
+        }
+        self.last_gpu_percent = Some((0.0, Instant::now()));
+        0.0
+    }
+
+    /// cpufreq's `scaling_cur_freq` averaged over all cores, else the "cpu MHz"
+    /// lines of /proc/cpuinfo (VMs often lack cpufreq).
+    fn read_cpu_freq_mhz(&mut self) -> f32 {
+        let Some(interval_ms) = self.intervals.cpu_freq_ms else {
+            return 0.0;
+        };
+        if let Some((cached, at)) = self.last_cpu_freq
+            && at.elapsed() < Duration::from_millis(interval_ms as u64)
+        {
+            return cached;
+        }
+
+        let mhz = read_cpu_freq_sysfs()
+            .or_else(read_cpu_freq_cpuinfo)
+            .unwrap_or(0.0);
+        self.last_cpu_freq = Some((mhz, Instant::now()));
+        mhz
+    }
+
+    /// `fan1_input` of the first hwmon device that has one.
+    fn read_fan_rpm(&mut self) -> u32 {
+        let Some(interval_ms) = self.intervals.fan_ms else {
+            return 0;
+        };
+        if let Some((cached, at)) = self.last_fan_rpm
+            && at.elapsed() < Duration::from_millis(interval_ms as u64)
+        {
+            return cached;
+        }
+
+        let rpm = read_fan_rpm_hwmon().unwrap_or(0);
+        self.last_fan_rpm = Some((rpm, Instant::now()));
+        rpm
+    }
+
+    fn read_cpu_temps(&mut self) {
+        let Some(interval_ms) = self.intervals.cpu_temp_ms else {
+            self.last_cpu_temps = None;
+            return;
+        };
+        if let Some((_, at)) = &self.last_cpu_temps
+            && at.elapsed() < Duration::from_millis(interval_ms as u64)
+        {
+            return;
+        }
+        let temps = read_cpu_temps_hwmon().unwrap_or_default();
+        self.last_cpu_temps = Some((temps, Instant::now()));
+    }
+
+    /// `brightness` over `max_brightness` of the pinned backlight LED, or else
+    /// the first `*kbd_backlight*` entry in /sys/class/leds. Looked up on every
+    /// poll so a replugged keyboard is found again.
+    fn read_kbd_backlight(&mut self) -> Option<f32> {
+        let interval_ms = self.intervals.backlight_ms?;
+        if let Some((cached, at)) = self.last_kbd_backlight
+            && at.elapsed() < Duration::from_millis(interval_ms as u64)
+        {
+            return cached;
+        }
+
+        let leds_root = Path::new("/sys/class/leds");
+        let dir = match &self.led_overrides.backlight {
+            Some(dir) => Some(leds_root.join(dir)),
+            None => fs::read_dir(leds_root).ok().and_then(|entries| {
+                let mut names: Vec<String> = entries
+                    .flatten()
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .filter(|name| name.to_lowercase().contains("kbd_backlight"))
+                    .collect();
+                names.sort();
+                names.first().map(|name| leds_root.join(name))
+            }),
+        };
+        let read = |file: &str| -> Option<f32> {
+            fs::read_to_string(dir.as_ref()?.join(file)).ok()?.trim().parse().ok()
+        };
+        let percent = match (read("brightness"), read("max_brightness")) {
+            (Some(level), Some(max)) if max > 0.0 => Some((level / max * 100.0).clamp(0.0, 100.0)),
+            _ => None,
+        };
+        self.last_kbd_backlight = Some((percent, Instant::now()));
+        percent
+    }
+
     fn read_volume_and_mute(&mut self) -> (f32, bool) {
         let volume_sample_interval = Duration::from_millis(self.intervals.volume_ms as u64);
 
@@ -266,6 +868,100 @@ impl MetricsCollector {
         result
     }
 
+    /// Scans /proc/*/stat and picks the process whose utime+stime grew most since
+    /// the previous scan. Processes that exit mid-scan are simply skipped.
+    fn read_top_process(&mut self) {
+        let Some(interval_ms) = self.intervals.process_ms else {
+            self.last_top_process = None;
+            self.last_proc_ticks.clear();
+            return;
+        };
+        let prev_at = self.last_top_process.as_ref().map(|(_, at)| *at);
+        if prev_at.is_some_and(|at| at.elapsed() < Duration::from_millis(interval_ms as u64)) {
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return;
+        };
+        let now = Instant::now();
+        let mut ticks = HashMap::with_capacity(self.last_proc_ticks.len());
+        let mut top: Option<(String, u64)> = None;
+
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+                continue;
+            };
+            let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            let Some((comm, used)) = parse_proc_stat(&stat) else {
+                continue;
+            };
+            ticks.insert(pid, used);
+
+            // New pids only get a baseline; they can win from the next scan.
+            let Some(&before) = self.last_proc_ticks.get(&pid) else {
+                continue;
+            };
+            let delta = used.saturating_sub(before);
+            if top.as_ref().is_none_or(|(_, best)| delta > *best) {
+                top = Some((comm.to_string(), delta));
+            }
+        }
+        self.last_proc_ticks = ticks;
+
+        let result = match (top, prev_at) {
+            (Some((name, delta)), Some(at)) => {
+                let clk_tck = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+                let cpus = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) }.max(1) as f64;
+                let elapsed = now.duration_since(at).as_secs_f64().max(0.001);
+                let percent = (delta as f64 / clk_tck / elapsed / cpus * 100.0) as f32;
+                (name, percent.clamp(0.0, 100.0))
+            }
+            _ => (String::new(), 0.0),
+        };
+        self.last_top_process = Some((result, now));
+    }
+
+    /// Title, position and length come from one playerctl call per interval,
+    /// run as a child and collected on a later tick like `read_pings`: a hung
+    /// D-Bus player must not freeze the dashboard.
+    fn read_media(&mut self) {
+        let Some(interval_ms) = self.intervals.media_ms else {
+            stop_child(&mut self.playerctl);
+            self.last_media = None;
+            return;
+        };
+        if self.playerctl.is_some() {
+            if let Some(text) = collect_child(&mut self.playerctl, PLAYERCTL_TIMEOUT) {
+                let media = parse_playerctl_media(&text).unwrap_or_default();
+                self.last_media = Some((media, Instant::now()));
+            }
+            return;
+        }
+        if let Some((_, at)) = &self.last_media
+            && at.elapsed() < Duration::from_millis(interval_ms as u64)
+        {
+            return;
+        }
+
+        match Command::new("playerctl")
+            .args([
+                "metadata",
+                "--format",
+                "{{artist}} - {{title}}\t{{position}}\t{{mpris:length}}",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => self.playerctl = Some((child, Instant::now())),
+            Err(_) => self.last_media = Some((MediaInfo::default(), Instant::now())),
+        }
+    }
+
     fn read_volume_mute_wpctl(&self) -> Option<(f32, bool)> {
         let output = Command::new("wpctl")
             .args(["get-volume", "@DEFAULT_AUDIO_SINK@"])
@@ -396,6 +1092,104 @@ impl MetricsCollector {
         }
     }
 
+    /// Polls running commands and starts the ones that are due. Never blocks:
+    /// output is collected on a later tick once the child has exited. Failed
+    /// or timed-out commands leave an empty string.
+    fn read_command_output(&mut self) {
+        for state in &mut self.commands {
+            if state.running.is_some() {
+                match collect_child(&mut state.running, COMMAND_TIMEOUT) {
+                    Some(text) => state.output = text.trim().to_string(),
+                    None => continue,
+                }
+            }
+
+            let interval = Duration::from_millis(
+                state.spec.interval_ms.unwrap_or(self.intervals.command_ms) as u64,
+            );
+            if state.last_run.is_some_and(|at| at.elapsed() < interval) {
+                continue;
+            }
+
+            state.last_run = Some(Instant::now());
+            match Command::new(&state.spec.program)
+                .args(&state.spec.args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => state.running = Some((child, Instant::now())),
+                Err(_) => state.output.clear(),
+            }
+        }
+    }
+
+    /// Same non-blocking polling as `read_command_output`: `ping -c1` runs as
+    /// a child and is collected on a later tick, so rendering never waits.
+    fn read_pings(&mut self) {
+        for state in &mut self.pings {
+            if state.running.is_some() {
+                match collect_child(&mut state.running, PING_TIMEOUT + Duration::from_secs(1)) {
+                    Some(text) => state.record(parse_ping_rtt(&text)),
+                    None => continue,
+                }
+            }
+
+            let interval =
+                Duration::from_millis(state.spec.interval_ms.unwrap_or(PING_INTERVAL_MS) as u64);
+            if state.last_run.is_some_and(|at| at.elapsed() < interval) {
+                continue;
+            }
+
+            state.last_run = Some(Instant::now());
+            let timeout = PING_TIMEOUT.as_secs().to_string();
+            match Command::new("ping")
+                .args(["-n", "-c1", "-W", &timeout, &state.spec.host])
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => state.running = Some((child, Instant::now())),
+                Err(_) => state.record(None),
+            }
+        }
+    }
+
+    /// `upower -d` per `devicebattery` widget, polled like `read_pings`.
+    fn read_device_batteries(&mut self) {
+        for state in &mut self.batteries {
+            if state.running.is_some() {
+                match collect_child(&mut state.running, COMMAND_TIMEOUT) {
+                    Some(text) => {
+                        state.percent = parse_upower_percentage(&text, &state.spec.device)
+                    }
+                    None => continue,
+                }
+            }
+
+            let interval = Duration::from_millis(
+                state.spec.interval_ms.unwrap_or(DEVICE_BATTERY_INTERVAL_MS) as u64,
+            );
+            if state.last_run.is_some_and(|at| at.elapsed() < interval) {
+                continue;
+            }
+
+            state.last_run = Some(Instant::now());
+            match Command::new("upower")
+                .arg("-d")
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => state.running = Some((child, Instant::now())),
+                Err(_) => state.percent = None,
+            }
+        }
+    }
+
     fn stop_audio_monitor(&mut self) {
         if let Some(mut capture) = self.audio_monitor.take() {
             let _ = capture.child.kill();
@@ -417,14 +1211,25 @@ impl MetricsCollector {
 
         self.last_audio_route_probe = Some(Instant::now());
         if let Some(sink) = self.default_sink_name_pactl() {
+            if self.cached_default_sink.as_ref() != Some(&sink) {
+                log::debug!("audio: default sink is {sink}");
+            }
             self.cached_default_sink = Some(sink);
         }
         if let Some(mon) = self.default_sink_monitor_source_pactl() {
+            if self.cached_monitor_source.as_ref() != Some(&mon) {
+                log::debug!("audio: monitoring {mon}");
+            }
             self.cached_monitor_source = Some(mon);
         }
     }
 
     fn ensure_audio_monitor(&mut self) -> Option<()> {
+        #[cfg(feature = "pipewire")]
+        if self.ensure_pipewire_monitor() {
+            return Some(());
+        }
+
         self.refresh_audio_route_if_needed(false);
         let sink_name = self.cached_default_sink.clone()?;
 
@@ -460,10 +1265,67 @@ impl MetricsCollector {
             return None;
         }
 
+        log::debug!("audio: started parec on {monitor_name}");
         self.audio_monitor = Some(AudioMonitorCapture { sink_name, child });
         Some(())
     }
 
+    /// Keeps a pw-record capture of the default sink's monitor running. Returns
+    /// false (and the parec path takes over) when pw-record is missing or keeps
+    /// exiting right after start, e.g. on a PulseAudio-only system.
+    #[cfg(feature = "pipewire")]
+    fn ensure_pipewire_monitor(&mut self) -> bool {
+        if self.pipewire_unavailable {
+            return false;
+        }
+        if let Some(existing) = &self.audio_monitor {
+            return existing.sink_name == PIPEWIRE_DEFAULT_SINK;
+        }
+        if self
+            .pipewire_started
+            .is_some_and(|at| at.elapsed() < Duration::from_secs(5))
+        {
+            // The last capture died within seconds of starting.
+            log::debug!("audio: pw-record keeps exiting, falling back to parec");
+            self.pipewire_unavailable = true;
+            return false;
+        }
+
+        let spawned = Command::new("pw-record")
+            .args([
+                "--raw",
+                "--format=s16",
+                "--rate=8000",
+                "--channels=1",
+                "--latency=20ms",
+                "-P",
+                "{ stream.capture.sink=true }",
+                "-",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = spawned else {
+            log::debug!("audio: pw-record unavailable, falling back to parec");
+            self.pipewire_unavailable = true;
+            return false;
+        };
+        if !Self::set_child_stdout_nonblocking(&mut child) {
+            let _ = child.kill();
+            let _ = child.wait();
+            self.pipewire_unavailable = true;
+            return false;
+        }
+
+        log::debug!("audio: started pw-record on the default sink");
+        self.pipewire_started = Some(Instant::now());
+        self.audio_monitor = Some(AudioMonitorCapture {
+            sink_name: PIPEWIRE_DEFAULT_SINK.to_string(),
+            child,
+        });
+        true
+    }
+
     fn set_child_stdout_nonblocking(child: &mut Child) -> bool {
         let Some(stdout) = child.stdout.as_ref() else {
             return false;
@@ -549,11 +1411,14 @@ impl MetricsCollector {
             return Some(0.0);
         }
 
-        let normalized = ((rms - 0.0008) / 0.018).clamp(0.0, 1.0);
+        let normalized =
+            ((rms - 0.0008) * self.intervals.audio_sensitivity / 0.018).clamp(0.0, 1.0);
         Some(normalized * 100.0)
     }
 
-    fn read_network_speed(&mut self, preferred_iface: Option<&str>) -> (f64, f64) {
+    /// Updates `net_rates` for every interface and returns the default
+    /// interface's (down, up); see `default_network_interface`.
+    fn read_network_speed(&mut self) -> (f64, f64) {
         let network_sample_interval = Duration::from_millis(self.intervals.network_ms as u64);
 
         if let Some((cached, at)) = self.last_network_speed
@@ -563,18 +1428,18 @@ impl MetricsCollector {
             return cached;
         }
 
-        let content = match fs::read_to_string("/proc/net/dev") {
-            Ok(v) => v,
-            Err(_) => return (0.0, 0.0),
+        let Some(content) = self.proc_files.net_dev.read() else {
+            return (0.0, 0.0);
         };
 
-        let mut chosen: Option<(String, u64, u64)> = None;
+        let now = Instant::now();
+        self.net_rates.clear();
 
         for line in content.lines().skip(2) {
             let Some((iface_raw, stats_raw)) = line.split_once(':') else {
                 continue;
             };
-            let iface = iface_raw.trim().to_string();
+            let iface = iface_raw.trim();
             if iface == "lo" {
                 continue;
             }
@@ -590,25 +1455,10 @@ impl MetricsCollector {
             let rx = stats[0];
             let tx = stats[8];
 
-            if let Some(preferred) = preferred_iface {
-                if iface == preferred {
-                    chosen = Some((iface, rx, tx));
-                    break;
-                }
-            } else if chosen.is_none() {
-                chosen = Some((iface, rx, tx));
-            }
-        }
-
-        let Some((iface, rx, tx)) = chosen else {
-            return (0.0, 0.0);
-        };
-
-        let now = Instant::now();
-        let (down_bps, up_bps) = if let Some(last) = &self.last_net {
-            if last.iface == iface {
-                if let Some(last_time) = last.at {
-                    let dt = now.duration_since(last_time).as_secs_f64();
+            // A newly seen interface only gets a baseline this round.
+            let rates = match self.last_net.get(iface) {
+                Some(last) => {
+                    let dt = now.duration_since(last.at).as_secs_f64();
                     if dt > 0.0 {
                         (
                             rx.saturating_sub(last.rx) as f64 / dt,
@@ -617,25 +1467,23 @@ impl MetricsCollector {
                     } else {
                         (0.0, 0.0)
                     }
-                } else {
-                    (0.0, 0.0)
                 }
-            } else {
-                (0.0, 0.0)
-            }
-        } else {
-            (0.0, 0.0)
-        };
+                None => (0.0, 0.0),
+            };
 
-        self.last_net = Some(NetSnapshot {
-            iface,
-            rx,
-            tx,
-            at: Some(now),
-        });
+            self.net_rates.insert(iface.to_string(), rates);
+            self.last_net
+                .insert(iface.to_string(), NetSnapshot { rx, tx, at: now });
+        }
 
-        let speeds = (down_bps, up_bps);
-        self.last_network_speed = Some((speeds, Instant::now()));
+        // Forget interfaces that disappeared (e.g. a VPN going down).
+        let rates = &self.net_rates;
+        self.last_net.retain(|iface, _| rates.contains_key(iface));
+
+        let speeds = default_network_interface(&self.net_rates)
+            .and_then(|iface| self.net_rates.get(&iface).copied())
+            .unwrap_or((0.0, 0.0));
+        self.last_network_speed = Some((speeds, now));
         speeds
     }
 
@@ -649,65 +1497,360 @@ impl MetricsCollector {
             return cached;
         }
 
-        if !self.led_paths_resolved {
+        // With no LEDs found yet (keyboard not plugged in), look again now and then.
+        let none_found = self.caps_led_path.is_none()
+            && self.num_led_path.is_none()
+            && self.scroll_led_path.is_none();
+        if !self.led_paths_resolved
+            || (none_found && self.led_paths_resolved_at.elapsed() >= LED_RESCAN_INTERVAL)
+        {
             self.resolve_keyboard_led_paths();
         }
 
-        let caps = self
-            .caps_led_path
-            .as_ref()
-            .map(|p| Self::read_led_brightness_bool(p))
-            .unwrap_or(false);
-        let num = self
-            .num_led_path
-            .as_ref()
-            .map(|p| Self::read_led_brightness_bool(p))
-            .unwrap_or(false);
-        let scroll = self
-            .scroll_led_path
-            .as_ref()
-            .map(|p| Self::read_led_brightness_bool(p))
-            .unwrap_or(false);
+        let caps = self.caps_led_path.as_ref().map(Self::read_led_brightness);
+        let num = self.num_led_path.as_ref().map(Self::read_led_brightness);
+        let scroll = self.scroll_led_path.as_ref().map(Self::read_led_brightness);
 
-        let leds = (caps, num, scroll);
+        // A cached path that can't be read any more means the keyboard was
+        // replugged and its sysfs entries renamed; rescan on the next sample.
+        if [caps, num, scroll].contains(&Some(None)) {
+            self.led_paths_resolved = false;
+        }
+
+        let leds = (
+            caps.flatten().unwrap_or(false),
+            num.flatten().unwrap_or(false),
+            scroll.flatten().unwrap_or(false),
+        );
         self.last_keyboard_leds = Some((leds, Instant::now()));
         leds
     }
 
     fn resolve_keyboard_led_paths(&mut self) {
         self.led_paths_resolved = true;
-
-        let entries = match fs::read_dir("/sys/class/leds") {
-            Ok(v) => v,
-            Err(_) => return,
+        self.led_paths_resolved_at = Instant::now();
+
+        let leds_root = Path::new("/sys/class/leds");
+        let mut names: Vec<String> = match fs::read_dir(leds_root) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        // `inputN::…` entries belong to the keyboard's input device, so they
+        // win over other matches; among those the lowest N does.
+        names.sort_by_key(|name| (led_input_number(name).unwrap_or(u32::MAX), name.clone()));
+
+        let find = |lock: &str| {
+            names
+                .iter()
+                .find(|name| led_matches(name, lock))
+                .map(|name| leds_root.join(name).join("brightness"))
+        };
+        let pinned = |dir: &Option<PathBuf>| {
+            dir.as_ref()
+                .map(|dir| leds_root.join(dir).join("brightness"))
         };
 
-        for entry in entries.flatten() {
-            let name = entry.file_name().to_string_lossy().to_lowercase();
-            let brightness = entry.path().join("brightness");
-            if name.contains("::capslock") {
-                self.caps_led_path = Some(brightness);
-            } else if name.contains("::numlock") {
-                self.num_led_path = Some(brightness);
-            } else if name.contains("::scrolllock") {
-                self.scroll_led_path = Some(brightness);
-            }
-        }
+        self.caps_led_path = pinned(&self.led_overrides.caps).or_else(|| find("caps"));
+        self.num_led_path = pinned(&self.led_overrides.num).or_else(|| find("num"));
+        self.scroll_led_path = pinned(&self.led_overrides.scroll).or_else(|| find("scroll"));
     }
 
-    fn read_led_brightness_bool(path: &PathBuf) -> bool {
-        fs::read_to_string(path)
-            .ok()
-            .and_then(|s| s.trim().parse::<u32>().ok())
-            .unwrap_or(0)
-            > 0
+    /// Whether the LED is lit; `None` when its brightness file can't be read.
+    fn read_led_brightness(path: &PathBuf) -> Option<bool> {
+        let raw = fs::read_to_string(path).ok()?;
+        Some(raw.trim().parse::<u32>().unwrap_or(0) > 0)
     }
 }
 
+/// LED names like `input3::capslock` or `kbd_capslock`: the part after the
+/// last `::` mentions both the lock (`caps`, `num`, `scroll`) and `lock`.
+fn led_matches(name: &str, lock: &str) -> bool {
+    let name = name.to_lowercase();
+    let function = name.rsplit("::").next().unwrap_or(&name);
+    function.contains(lock) && function.contains("lock")
+}
+
+/// N of an `inputN::…` LED name.
+fn led_input_number(name: &str) -> Option<u32> {
+    let rest = name.strip_prefix("input")?;
+    let (number, _) = rest.split_once("::")?;
+    number.parse().ok()
+}
+
 impl Drop for MetricsCollector {
     fn drop(&mut self) {
         self.stop_audio_monitor();
+        for state in &mut self.commands {
+            stop_child(&mut state.running);
+        }
+        for state in &mut self.pings {
+            stop_child(&mut state.running);
+        }
+        for state in &mut self.batteries {
+            stop_child(&mut state.running);
+        }
+        stop_child(&mut self.nvidia_smi);
+        stop_child(&mut self.playerctl);
+    }
+}
+
+/// Returns `comm` and utime+stime from a /proc/<pid>/stat line. `comm` may
+/// contain spaces and parentheses, so fields are counted from the last `)`.
+fn parse_proc_stat(stat: &str) -> Option<(&str, u64)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?;
+    let mut fields = stat.get(close + 1..)?.split_whitespace();
+    // After comm: state is field 3, utime 14 and stime 15 (1-based).
+    let utime = fields.nth(11)?.parse::<u64>().ok()?;
+    let stime = fields.next()?.parse::<u64>().ok()?;
+    Some((comm, utime + stime))
+}
+
+fn read_gpu_busy_sysfs() -> Option<f32> {
+    let entries = fs::read_dir("/sys/class/drm").ok()?;
+    let mut cards: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("card"))
+                .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .collect();
+    cards.sort();
+    cards.iter().find_map(|card| {
+        fs::read_to_string(card.join("device/gpu_busy_percent"))
+            .ok()?
+            .trim()
+            .parse::<f32>()
+            .ok()
+    })
+}
+
+fn read_cpu_freq_sysfs() -> Option<f32> {
+    let entries = fs::read_dir("/sys/devices/system/cpu").ok()?;
+    let khz: Vec<f32> = entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("cpu"))
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .filter_map(|entry| {
+            fs::read_to_string(entry.path().join("cpufreq/scaling_cur_freq"))
+                .ok()?
+                .trim()
+                .parse::<f32>()
+                .ok()
+        })
+        .collect();
+    average(&khz).map(|khz| khz / 1000.0)
+}
+
+fn read_cpu_freq_cpuinfo() -> Option<f32> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    let mhz: Vec<f32> = cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("cpu MHz"))
+        .filter_map(|line| line.split_once(':')?.1.trim().parse::<f32>().ok())
+        .collect();
+    average(&mhz)
+}
+
+fn read_fan_rpm_hwmon() -> Option<u32> {
+    let entries = fs::read_dir("/sys/class/hwmon").ok()?;
+    let mut devices: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    devices.sort();
+    devices.iter().find_map(|device| {
+        fs::read_to_string(device.join("fan1_input"))
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()
+    })
+}
+
+/// `temp*_input` of the coretemp (Intel) or k10temp (AMD) hwmon device. The
+/// package is `Package id 0`/`Tctl`, falling back to the first sensor; cores
+/// are the `Core N` or `TccdN` entries.
+fn read_cpu_temps_hwmon() -> Option<CpuTemps> {
+    let entries = fs::read_dir("/sys/class/hwmon").ok()?;
+    let device = entries.flatten().map(|entry| entry.path()).find(|device| {
+        fs::read_to_string(device.join("name"))
+            .is_ok_and(|name| matches!(name.trim(), "coretemp" | "k10temp"))
+    })?;
+
+    // (sensor number, label, °C), sorted so cores come out in order.
+    let mut sensors: Vec<(u32, String, f32)> = fs::read_dir(&device)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let n = name
+                .to_str()?
+                .strip_prefix("temp")?
+                .strip_suffix("_input")?
+                .parse::<u32>()
+                .ok()?;
+            let millidegrees = fs::read_to_string(entry.path())
+                .ok()?
+                .trim()
+                .parse::<f32>()
+                .ok()?;
+            let label = fs::read_to_string(device.join(format!("temp{n}_label")))
+                .unwrap_or_default();
+            Some((n, label.trim().to_string(), millidegrees / 1000.0))
+        })
+        .collect();
+    sensors.sort_by_key(|(n, ..)| *n);
+
+    let package = sensors
+        .iter()
+        .find(|(_, label, _)| label.starts_with("Package") || label == "Tctl")
+        .or(sensors.first())
+        .map(|&(_, _, temp)| temp)?;
+    let cores = sensors
+        .iter()
+        .filter(|(_, label, _)| label.starts_with("Core ") || label.starts_with("Tccd"))
+        .map(|&(_, _, temp)| temp)
+        .collect();
+    Some(CpuTemps { package, cores })
+}
+
+fn average(values: &[f32]) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// `nvidia-smi --query-gpu=utilization.gpu` output: one line per GPU; the
+/// first one is shown.
+fn parse_nvidia_smi(output: &str) -> Option<f32> {
+    output.lines().next()?.trim().parse::<f32>().ok()
+}
+
+/// Interface for network widgets without an explicit `interface`: the one
+/// carrying the IPv4 default route if it's up, else the busiest one right now.
+/// Bridges and virtual devices listed first in /proc/net/dev are usually idle,
+/// so "first non-loopback" alone picks the wrong one.
+fn default_network_interface(rates: &HashMap<String, (f64, f64)>) -> Option<String> {
+    let routed = fs::read_to_string("/proc/net/route").ok().and_then(|table| {
+        table.lines().skip(1).find_map(|line| {
+            let mut cols = line.split_whitespace();
+            let iface = cols.next()?;
+            (cols.next()? == "00000000").then(|| iface.to_string())
+        })
+    });
+    if let Some(iface) = routed
+        && rates.contains_key(&iface)
+        && fs::read_to_string(format!("/sys/class/net/{iface}/operstate"))
+            .is_ok_and(|state| state.trim() != "down")
+    {
+        return Some(iface);
+    }
+
+    rates
+        .iter()
+        .max_by(|(a_name, (a_down, a_up)), (b_name, (b_down, b_up))| {
+            (a_down + a_up)
+                .total_cmp(&(b_down + b_up))
+                // Stable pick among idle interfaces so the default doesn't flap.
+                .then_with(|| b_name.cmp(a_name))
+        })
+        .map(|(iface, _)| iface.clone())
+}
+
+/// Collects a background child once it has exited: `Some` of its stdout, empty
+/// when it failed. `None` while it is still running. One running longer than
+/// `timeout` is killed and counts as failed.
+fn collect_child(running: &mut Option<(Child, Instant)>, timeout: Duration) -> Option<String> {
+    let (child, started) = running.as_mut()?;
+    match child.try_wait() {
+        Ok(Some(status)) => {
+            let mut text = String::new();
+            if status.success()
+                && let Some(mut stdout) = child.stdout.take()
+            {
+                let _ = stdout.read_to_string(&mut text);
+            }
+            *running = None;
+            Some(text)
+        }
+        Ok(None) if started.elapsed() > timeout => {
+            stop_child(running);
+            Some(String::new())
+        }
+        Ok(None) => None,
+        Err(_) => {
+            *running = None;
+            Some(String::new())
+        }
+    }
+}
+
+fn stop_child(running: &mut Option<(Child, Instant)>) {
+    if let Some((mut child, _)) = running.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// `percentage:` of the first `upower -d` device block whose object path,
+/// `native-path` or `model` contains `device` (case-insensitive).
+fn parse_upower_percentage(output: &str, device: &str) -> Option<f32> {
+    let device = device.to_lowercase();
+    let mut matched = false;
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("Device:") {
+            matched = path.to_lowercase().contains(&device);
+        } else if let Some(value) = line
+            .strip_prefix("native-path:")
+            .or_else(|| line.strip_prefix("model:"))
+        {
+            matched |= value.to_lowercase().contains(&device);
+        } else if matched && let Some(value) = line.strip_prefix("percentage:") {
+            return value.trim().trim_end_matches('%').trim().parse::<f32>().ok();
+        }
+    }
+    None
+}
+
+/// The `time=12.3 ms` of a `ping -c1` reply.
+fn parse_ping_rtt(output: &str) -> Option<f32> {
+    let rest = &output[output.find("time=")? + "time=".len()..];
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    rest[..end].parse::<f32>().ok()
+}
+
+/// Parses `title\tposition\tlength` (times in microseconds, length may be empty).
+fn parse_playerctl_media(text: &str) -> Option<MediaInfo> {
+    let line = text.trim();
+    if line.starts_with("No players found") {
+        return None;
+    }
+    let mut fields = line.split('\t');
+    let title = fields.next()?.trim();
+    if title.is_empty() || title == "-" {
+        return None;
     }
+    let micros = |field: Option<&str>| {
+        field
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .map_or(0.0, |us| us / 1_000_000.0)
+    };
+    Some(MediaInfo {
+        title: title.to_string(),
+        position: micros(fields.next()),
+        length: micros(fields.next()),
+    })
 }
 
 fn first_number(input: &str) -> f32 {
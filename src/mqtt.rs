@@ -0,0 +1,279 @@
+//! Publishes each `MetricsSample` as JSON over MQTT 3.1.1 (QoS 0) from a
+//! background thread, so a slow or unreachable broker never holds up a frame.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::config::MqttConfig;
+use crate::log;
+use crate::metrics::MetricsSample;
+
+// Batches waiting for the broker; newer ones are dropped once it's full.
+const QUEUE_LEN: usize = 8;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const IO_TIMEOUT: Duration = Duration::from_secs(3);
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const RETRY_BASE: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// (topic, JSON payload) pairs published together.
+type Batch = Vec<(String, String)>;
+
+pub struct MqttPublisher {
+    queue: SyncSender<Batch>,
+    prefix: String,
+    interval: Duration,
+    last_publish: Option<Instant>,
+    dropped: u64,
+}
+
+impl MqttPublisher {
+    /// Starts the publisher thread. The broker is connected lazily, and
+    /// reconnected with backoff whenever a write fails.
+    pub fn start(config: &MqttConfig) -> Result<Self> {
+        let addr = config
+            .broker_addr()
+            .with_context(|| format!("invalid mqtt broker {}", config.broker))?;
+        let (queue, batches) = mpsc::sync_channel(QUEUE_LEN);
+        let mut connection = Connection {
+            addr,
+            client_id: config
+                .client_id
+                .clone()
+                .unwrap_or_else(|| format!("stained-steel-{}", std::process::id())),
+            username: config.username.clone(),
+            password: config.password.clone(),
+            retain: config.retain,
+            stream: None,
+            failures: 0,
+            retry_at: None,
+        };
+        thread::Builder::new()
+            .name("mqtt".to_string())
+            .spawn(move || connection.run(batches))
+            .context("failed to start the mqtt thread")?;
+        Ok(Self {
+            queue,
+            prefix: config.topic_prefix.clone(),
+            interval: Duration::from_millis(config.interval_ms as u64),
+            last_publish: None,
+            dropped: 0,
+        })
+    }
+
+    /// Queues `sample` for publishing unless the last one is less than
+    /// `interval_ms` old. Never blocks: a full queue drops the sample.
+    pub fn publish(&mut self, sample: &MetricsSample) {
+        if self.last_publish.is_some_and(|at| at.elapsed() < self.interval) {
+            return;
+        }
+        self.last_publish = Some(Instant::now());
+
+        let interfaces: serde_json::Map<String, serde_json::Value> = sample
+            .net_rates
+            .iter()
+            .map(|(name, &(down, up))| (name.clone(), json!({ "down_bps": down, "up_bps": up })))
+            .collect();
+        let topic = |name: &str| format!("{}/{name}", self.prefix);
+        let batch = vec![
+            (
+                topic("cpu"),
+                json!({ "percent": sample.cpu_percent, "cores": sample.cpu_core_percents })
+                    .to_string(),
+            ),
+            (
+                topic("memory"),
+                json!({
+                    "percent": sample.mem_percent,
+                    "used_gb": sample.mem_used_gb,
+                    "total_gb": sample.mem_total_gb,
+                })
+                .to_string(),
+            ),
+            (
+                topic("volume"),
+                json!({ "percent": sample.volume_percent, "muted": sample.is_muted }).to_string(),
+            ),
+            (
+                topic("network"),
+                json!({
+                    "down_bps": sample.net_down_bps,
+                    "up_bps": sample.net_up_bps,
+                    "interfaces": interfaces,
+                })
+                .to_string(),
+            ),
+            (
+                topic("leds"),
+                json!({
+                    "caps_lock": sample.caps_lock,
+                    "num_lock": sample.num_lock,
+                    "scroll_lock": sample.scroll_lock,
+                })
+                .to_string(),
+            ),
+        ];
+
+        match self.queue.try_send(batch) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                if self.dropped.is_power_of_two() {
+                    log::debug!("mqtt: broker is behind, dropped {} samples", self.dropped);
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+struct Connection {
+    addr: String,
+    client_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    retain: bool,
+    stream: Option<TcpStream>,
+    // Reconnect backoff, like the keyboard's: failures in a row and when to retry.
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Connection {
+    fn run(&mut self, batches: Receiver<Batch>) {
+        loop {
+            // Wake up for keep-alive pings while nothing is published.
+            match batches.recv_timeout(KEEP_ALIVE / 2) {
+                Ok(batch) => {
+                    if let Err(err) = self.send_batch(&batch) {
+                        self.fail(err);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(stream) = self.stream.as_mut()
+                        && let Err(err) = stream.write_all(&[0xC0, 0x00])
+                    {
+                        self.fail(err);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(stream) = self.stream.as_mut() {
+                        let _ = stream.write_all(&[0xE0, 0x00]);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn send_batch(&mut self, batch: &Batch) -> io::Result<()> {
+        if self.stream.is_none() {
+            // Samples that arrive during the backoff are dropped, not queued.
+            if self.retry_at.is_some_and(|at| Instant::now() < at) {
+                return Ok(());
+            }
+            self.stream = Some(self.connect()?);
+            if self.failures > 0 {
+                log::info!("mqtt: reconnected to {}", self.addr);
+            }
+            self.failures = 0;
+            self.retry_at = None;
+        }
+        let stream = self.stream.as_mut().expect("connected above");
+        let mut packet = Vec::new();
+        for (topic, payload) in batch {
+            let flags = if self.retain { 0x31 } else { 0x30 };
+            let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+            push_str(&mut body, topic);
+            body.extend_from_slice(payload.as_bytes());
+            push_packet(&mut packet, flags, &body);
+        }
+        stream.write_all(&packet)
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let addr = self
+            .addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "broker has no address"))?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        let mut flags = 0x02; // clean session
+        let mut body = Vec::new();
+        push_str(&mut body, "MQTT");
+        body.push(4); // protocol level 3.1.1
+        if self.username.is_some() {
+            flags |= 0x80;
+        }
+        if self.password.is_some() {
+            flags |= 0x40;
+        }
+        body.push(flags);
+        body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+        push_str(&mut body, &self.client_id);
+        for field in [&self.username, &self.password].into_iter().flatten() {
+            push_str(&mut body, field);
+        }
+        let mut packet = Vec::new();
+        push_packet(&mut packet, 0x10, &body);
+        stream.write_all(&packet)?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::other(format!(
+                "broker refused the connection (CONNACK code {})",
+                connack[3]
+            )));
+        }
+        Ok(stream)
+    }
+
+    fn fail(&mut self, err: io::Error) {
+        self.stream = None;
+        let delay = RETRY_BASE
+            .saturating_mul(1 << self.failures.min(6))
+            .min(RETRY_MAX);
+        if self.failures == 0 {
+            log::warn!("mqtt: {}: {err}; retrying in {delay:?}", self.addr);
+        } else {
+            log::debug!("mqtt: {}: {err}; retrying in {delay:?}", self.addr);
+        }
+        self.failures = self.failures.saturating_add(1);
+        self.retry_at = Some(Instant::now() + delay);
+    }
+}
+
+/// UTF-8 string with its u16 length, as MQTT encodes topics and names.
+fn push_str(out: &mut Vec<u8>, value: &str) {
+    let bytes = &value.as_bytes()[..value.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Fixed header (`kind` byte, variable-length remaining length) then `body`.
+fn push_packet(out: &mut Vec<u8>, kind: u8, body: &[u8]) {
+    out.push(kind);
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+}
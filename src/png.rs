@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Writes a packed frame (row-major, MSB-first 1bpp, as sent to the device)
+/// as a grayscale PNG where every OLED pixel becomes a `scale`×`scale` block.
+pub fn write_png(
+    path: &Path,
+    frame: &[u8],
+    width: usize,
+    height: usize,
+    scale: usize,
+) -> Result<()> {
+    let data = encode_png(frame, width, height, scale);
+    fs::write(path, data).with_context(|| format!("failed writing {}", path.display()))
+}
+
+/// Minimal PNG encoder: 8-bit grayscale, no filtering, stored (uncompressed)
+/// deflate blocks. Frames are tiny, so compression isn't worth a dependency.
+pub fn encode_png(frame: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let out_w = width * scale;
+    let out_h = height * scale;
+
+    let mut raw = Vec::with_capacity((out_w + 1) * out_h);
+    for y in 0..out_h {
+        raw.push(0); // filter type: none
+        let src_y = y / scale;
+        for x in 0..out_w {
+            let bit = src_y * width + x / scale;
+            let lit = frame
+                .get(bit / 8)
+                .is_some_and(|byte| byte & (0x80 >> (bit % 8)) != 0);
+            raw.push(if lit { 0xFF } else { 0x00 });
+        }
+    }
+
+    let mut png = Vec::with_capacity(raw.len() + raw.len() / 65535 * 5 + 64);
+    png.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(out_w as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(out_h as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 0, 0, 0, 0]); // 8-bit, grayscale, deflate, no filter, no interlace
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 16);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(u8::from(last));
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,77 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+
+use crate::sink::FrameSink;
+
+/// Prints packed frames to the terminal using half-block characters,
+/// so two OLED rows fit in one text row.
+pub struct TerminalPreview {
+    width: usize,
+    height: usize,
+    first_frame: bool,
+    out: String,
+}
+
+impl TerminalPreview {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            first_frame: true,
+            out: String::with_capacity((width + 1) * height.div_ceil(2) * 3 + 16),
+        }
+    }
+}
+
+impl FrameSink for TerminalPreview {
+    fn send_frame(&mut self, frame: &[u8]) -> Result<()> {
+        self.out.clear();
+        if self.first_frame {
+            // Clear once, then only home the cursor to avoid flicker.
+            self.out.push_str("\x1b[2J");
+            self.first_frame = false;
+        }
+        self.out.push_str("\x1b[H");
+
+        for y in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let top = packed_pixel(frame, self.width, x, y);
+                let bottom = y + 1 < self.height && packed_pixel(frame, self.width, x, y + 1);
+                self.out.push(match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            self.out.push('\n');
+        }
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(self.out.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Renders a packed frame as plain text, `#` for lit and `.` for unlit, one
+/// line per OLED row. Easy to diff and review, unlike raw frame bytes.
+pub fn frame_to_ascii(frame: &[u8], width: usize, height: usize) -> String {
+    let mut out = String::with_capacity((width + 1) * height);
+    for y in 0..height {
+        for x in 0..width {
+            out.push(if packed_pixel(frame, width, x, y) { '#' } else { '.' });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Reads one pixel back from a row-major, MSB-first packed frame.
+fn packed_pixel(frame: &[u8], width: usize, x: usize, y: usize) -> bool {
+    let bit = y * width + x;
+    frame
+        .get(bit / 8)
+        .is_some_and(|byte| byte & (0x80 >> (bit % 8)) != 0)
+}
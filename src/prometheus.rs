@@ -0,0 +1,119 @@
+//! `--metrics-addr <addr>`: the latest `MetricsSample` in the Prometheus text
+//! exposition format at `/metrics`, served by the `http` module.
+
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use crate::http::{self, Response};
+use crate::log;
+use crate::metrics::MetricsSample;
+
+/// Copy of the latest sample; the render loop updates it every tick and the
+/// server thread formats it on each scrape.
+#[derive(Clone, Default)]
+pub struct MetricsShare {
+    sample: Arc<Mutex<Option<MetricsSample>>>,
+}
+
+impl MetricsShare {
+    pub fn update(&self, sample: &MetricsSample) {
+        let mut shared = self.sample.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.as_mut() {
+            // Reuses the previous sample's buffers.
+            Some(latest) => latest.clone_from(sample),
+            None => *shared = Some(sample.clone()),
+        }
+    }
+}
+
+pub fn serve_metrics(addr: &str, share: MetricsShare) -> Result<()> {
+    let local = http::serve(addr, move |path| {
+        if path != "/metrics" {
+            return Response::text("404 Not Found", "metrics are at /metrics\n");
+        }
+        let text = {
+            let sample = share.sample.lock().unwrap_or_else(|e| e.into_inner());
+            match sample.as_ref() {
+                Some(sample) => exposition(sample),
+                None => return Response::text("503 Service Unavailable", "no sample yet\n"),
+            }
+        };
+        Response::ok("text/plain; version=0.0.4", text.into_bytes())
+    })?;
+    log::info!("Serving metrics on http://{local}/metrics");
+    Ok(())
+}
+
+fn exposition(sample: &MetricsSample) -> String {
+    let mut out = String::with_capacity(2048);
+    let help = "Busy share of all CPUs (0-100).";
+    gauge(&mut out, "stained_cpu_percent", help, sample.cpu_percent);
+    family(&mut out, "stained_cpu_core_percent", "Busy share of each logical CPU (0-100).");
+    for (core, percent) in sample.cpu_core_percents.iter().enumerate() {
+        let _ = writeln!(out, "stained_cpu_core_percent{{core=\"{core}\"}} {percent}");
+    }
+    if sample.cpu_freq_mhz > 0.0 {
+        let help = "Average current CPU clock in MHz.";
+        gauge(&mut out, "stained_cpu_freq_mhz", help, sample.cpu_freq_mhz);
+    }
+    if sample.cpu_temp > 0.0 {
+        let help = "CPU package temperature in degrees Celsius.";
+        gauge(&mut out, "stained_cpu_temp_celsius", help, sample.cpu_temp);
+    }
+    gauge(&mut out, "stained_mem_percent", "Used share of RAM (0-100).", sample.mem_percent);
+    let gib = 1024.0 * 1024.0 * 1024.0;
+    let used = sample.mem_used_gb as f64 * gib;
+    gauge(&mut out, "stained_mem_used_bytes", "Total minus available RAM.", used);
+    let total = sample.mem_total_gb as f64 * gib;
+    gauge(&mut out, "stained_mem_total_bytes", "Total RAM.", total);
+    let help = "Sink volume (0-100), kept while muted.";
+    gauge(&mut out, "stained_volume_percent", help, sample.volume_percent);
+    gauge(&mut out, "stained_muted", "1 while the sink is muted.", flag(sample.is_muted));
+    let help = "Download rate of the default interface in bytes/s.";
+    gauge(&mut out, "stained_net_down_bps", help, sample.net_down_bps);
+    let help = "Upload rate of the default interface in bytes/s.";
+    gauge(&mut out, "stained_net_up_bps", help, sample.net_up_bps);
+    let mut interfaces: Vec<_> = sample.net_rates.iter().collect();
+    interfaces.sort_by(|a, b| a.0.cmp(b.0));
+    let help = "Download rate per interface in bytes/s.";
+    family(&mut out, "stained_net_interface_down_bps", help);
+    for (iface, (down, _)) in &interfaces {
+        let iface = escape_label(iface);
+        let _ = writeln!(out, "stained_net_interface_down_bps{{interface=\"{iface}\"}} {down}");
+    }
+    let help = "Upload rate per interface in bytes/s.";
+    family(&mut out, "stained_net_interface_up_bps", help);
+    for (iface, (_, up)) in &interfaces {
+        let iface = escape_label(iface);
+        let _ = writeln!(out, "stained_net_interface_up_bps{{interface=\"{iface}\"}} {up}");
+    }
+    gauge(&mut out, "stained_caps_lock", "1 while Caps Lock is on.", flag(sample.caps_lock));
+    gauge(&mut out, "stained_num_lock", "1 while Num Lock is on.", flag(sample.num_lock));
+    let help = "1 while Scroll Lock is on.";
+    gauge(&mut out, "stained_scroll_lock", help, flag(sample.scroll_lock));
+    if let Some(level) = sample.kbd_backlight {
+        let help = "Keyboard backlight brightness (0-100).";
+        gauge(&mut out, "stained_kbd_backlight_percent", help, level);
+    }
+    out
+}
+
+fn family(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    family(out, name, help);
+    let _ = writeln!(out, "{name} {value}");
+}
+
+fn flag(on: bool) -> u8 {
+    u8::from(on)
+}
+
+/// Label values escape backslash, double quote and newline.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
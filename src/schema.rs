@@ -0,0 +1,383 @@
+//! `--dump-config-schema`: a JSON Schema (draft 2020-12) of the config file,
+//! for editor completion and validation. Written by hand next to the serde
+//! structs in `config`, so it has to be updated with them. `DashboardConfig::load`
+//! also checks files against its property lists to point out unknown keys.
+
+use serde_json::{Map, Value, json};
+
+use crate::config::{
+    AudioConfig, BAR_DIRECTIONS, BootConfig, DEFAULT_PROFILE, DEFAULT_WEATHER_MS,
+    KNOWN_WIDGET_KINDS,
+};
+
+pub fn config_schema() -> Value {
+    let boot = BootConfig::default();
+    let audio = AudioConfig::default();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "StainedSteel dashboard config",
+        "type": "object",
+        "required": ["display"],
+        "properties": {
+            "$schema": string("Where editors find this schema; not read."),
+            "config_name": string("Name shown in the startup log.").with_default(""),
+            "refresh_rate_ms": integer("Frame interval; 16 at least.").with_default(33),
+            "idle_refresh_ms": optional(integer(
+                "Slowest frame interval to back off to while frames stay identical.",
+            )),
+            "idle_blank_ms": optional(integer(
+                "Blank the panel after this long without a changed frame.",
+            )),
+            "idle_mode": one_of(&["blank", "bounce"], "What the idle panel shows.")
+                .with_default("blank"),
+            "idle_sprite": optional(string(
+                "Icon file for idle_mode bounce, relative to the config.",
+            )),
+            "burn_in_shift_ms": optional(integer(
+                "Nudge the frame by up to 1px this often against burn-in.",
+            )),
+            "display": {
+                "type": "object",
+                "required": ["width", "height"],
+                "properties": {
+                    "width": integer("Panel width in pixels; 128 on the Apex 5."),
+                    "height": integer("Panel height in pixels; 40 on the Apex 5."),
+                    "background": integer("Non-zero starts every frame lit.").with_default(0),
+                    "brightness": optional(integer(
+                        "Experimental; needs device.brightness_report.",
+                    )),
+                    "rotation": {
+                        "enum": [0, 180],
+                        "default": 0,
+                        "description": "Degrees, for a keyboard mounted upside down.",
+                    },
+                    "mirror": one_of(&["none", "horizontal", "vertical"], "Applied after rotation.")
+                        .with_default("none"),
+                },
+            },
+            "device": {
+                "type": "object",
+                "description": "USB identity and report layout; unset fields are the Apex 5 ones.",
+                "properties": {
+                    "vendor_id": hex("USB vendor id.").with_default("0x1038"),
+                    "product_id": hex("USB product id.").with_default("0x161C"),
+                    "interface": optional(string("USB interface as mi_NN.")).with_default("mi_01"),
+                    "path": optional(string("Explicit hidraw node; skips discovery.")),
+                    "sysfs_path": optional(string(
+                        "Only discover nodes whose sysfs device path contains this.",
+                    )),
+                    "report_id": hex("First byte of every output report.").with_default("0x61"),
+                    "packet_len": optional(integer(
+                        "Report length: report id, packed frame and 1 padding byte.",
+                    )),
+                    "read_input": boolean("Experimental: poll the input reports the device sends.")
+                        .with_default(false),
+                    "brightness_report": hex("Experimental: report id for display.brightness."),
+                },
+            },
+            "boot": {
+                "type": "object",
+                "properties": {
+                    "enabled": boolean("Show the startup animation.").with_default(boot.enabled),
+                    "duration_ms": integer("Length of the boot phase.")
+                        .with_default(boot.duration_ms),
+                    "style": one_of(&["gear", "blank", "none"], "Boot animation.")
+                        .with_default("gear"),
+                },
+            },
+            "audio": {
+                "type": "object",
+                "description": "Tuning for the volume widget's output meter.",
+                "properties": {
+                    "noise_floor": number("Level (0-100) treated as silence.")
+                        .with_default(audio.noise_floor),
+                    "sensitivity": number("Gain on the captured signal.")
+                        .with_default(audio.sensitivity),
+                    "smoothing": number("Share of the previous level kept each sample (0-1).")
+                        .with_default(audio.smoothing),
+                    "audio_noise_floor": deprecated("Old name of noise_floor."),
+                    "audio_sensitivity": deprecated("Old name of sensitivity."),
+                    "audio_smoothing": deprecated("Old name of smoothing."),
+                },
+            },
+            "keyboard_leds": {
+                "type": "object",
+                "description": "LED directories, relative to /sys/class/leds or absolute.",
+                "properties": {
+                    "capslock": optional(string("Caps Lock LED.")),
+                    "numlock": optional(string("Num Lock LED.")),
+                    "scrolllock": optional(string("Scroll Lock LED.")),
+                    "backlight": optional(string("Backlight LED for the backlight widget.")),
+                },
+            },
+            "transition": optional(json!({
+                "type": "object",
+                "description": "Animation on a reload or profile switch; unset cuts over.",
+                "properties": {
+                    "style": one_of(&["dissolve", "wipe"], "How the new layout appears.")
+                        .with_default("dissolve"),
+                    "duration_ms": integer("Length of the animation; 0 cuts over.")
+                        .with_default(400),
+                },
+            })),
+            "mqtt": optional(json!({
+                "type": "object",
+                "description": "Publish the metrics over MQTT (needs the mqtt feature).",
+                "required": ["broker"],
+                "properties": {
+                    "broker": string("mqtt://host[:port]; the port defaults to 1883."),
+                    "topic_prefix": string("Topics are <prefix>/cpu, /memory, ...")
+                        .with_default("stained_steel"),
+                    "client_id": optional(string("Defaults to stained-steel-<pid>.")),
+                    "username": optional(string("Broker user name.")),
+                    "password": optional(string("Broker password.")),
+                    "interval_ms": integer("Shortest time between publishes; 0 is every frame.")
+                        .with_default(1000),
+                    "retain": boolean("Ask the broker to keep the last message.")
+                        .with_default(false),
+                },
+            })),
+            "widgets": {
+                "type": "array",
+                "description": format!("The \"{DEFAULT_PROFILE}\" profile."),
+                "items": { "$ref": "#/$defs/widget" },
+                "default": [],
+            },
+            "profiles": {
+                "type": "object",
+                "description": "Named alternative widget sets, cycled with SIGUSR1/SIGUSR2.",
+                "additionalProperties": {
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/widget" },
+                },
+                "default": {},
+            },
+        },
+        "$defs": {
+            "widget": widget_schema(),
+            "position": {
+                "type": "object",
+                "required": ["x", "y", "w", "h"],
+                "properties": {
+                    "x": integer("Left edge."),
+                    "y": integer("Top edge."),
+                    "w": integer("Width."),
+                    "h": integer("Height."),
+                    "z": integer("Stacking order from the Go version; not read."),
+                },
+            },
+            "bar": {
+                "type": "object",
+                "properties": {
+                    "direction": one_of(BAR_DIRECTIONS, "Axis and direction of the fill.")
+                        .with_default("horizontal"),
+                    "border": boolean("Draw a border.").with_default(false),
+                    "border_thickness": integer("Border width when border is set.")
+                        .with_default(1),
+                    "show_value": boolean("Draw the percentage over the bar.").with_default(false),
+                    "style": one_of(&["solid", "segments"], "Fill style.").with_default("solid"),
+                    "segments": integer("Block count for the segments style.").with_default(10),
+                    "min": number("Value of an empty bar.").with_default(0.0),
+                    "max": number("Value of a full bar.").with_default(100.0),
+                },
+            },
+            "graph": {
+                "type": "object",
+                "properties": {
+                    "history": integer("Samples kept; 0 fits the widget width.").with_default(0),
+                    "autoscale": boolean("Scale to the highest value in the history.")
+                        .with_default(false),
+                    "gridlines": {
+                        "type": "array",
+                        "items": { "type": "number", "minimum": 0, "maximum": 100 },
+                        "description": "Dotted guide lines at these percentages of the height.",
+                        "default": [],
+                    },
+                },
+            },
+        },
+    })
+}
+
+fn widget_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["type", "position"],
+        "properties": {
+            "type": one_of(KNOWN_WIDGET_KINDS, "What the widget shows."),
+            "enabled": boolean("Disabled widgets are skipped.").with_default(true),
+            "refresh_rate_ms": optional(integer("How often its metric is sampled.")),
+            "render_interval_ms": optional(integer(
+                "Redraw at most this often; other frames reuse its pixels.",
+            )),
+            "position": { "$ref": "#/$defs/position" },
+            "interface": optional(string("network: interface; unset uses the default one.")),
+            "show_icon": boolean("Draw the widget's icon.").with_default(false),
+            "show_seconds": boolean("analogclock: draw a second hand.").with_default(false),
+            "source": optional(string(
+                "gauge/sparkline: cpu, memory, gpu or volume. barchart: cpucores or coretemps.",
+            )),
+            "bar": optional(json!({ "$ref": "#/$defs/bar" })),
+            "graph": optional(json!({ "$ref": "#/$defs/graph" })),
+            "mode": optional(string(
+                "network: text|graph. memory: percent|absolute|graph|bar. cpufreq: ghz|mhz. \
+                 cputemp: text|cores. backlight: bar|sun.",
+            )),
+            "units": optional(one_of(&["bytes", "bits"], "Network rate units.")),
+            "location": optional(string("weather: fixed \"lat,lon\"; unset locates by IP.")),
+            "url": optional(string("weather: custom Open-Meteo or wttr.in endpoint.")),
+            "weather_ms": optional(integer("weather: ms between fetches; at least 60000."))
+                .with_default(DEFAULT_WEATHER_MS),
+            "host": optional(string("ping: host name or address.")),
+            "device": optional(string("devicebattery: UPower model, native path or object path.")),
+            "command": optional(string("command: program whose stdout is shown.")),
+            "args": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "command: arguments.",
+                "default": [],
+            },
+            "icon": optional(string("Icon file (PBM, PGM or JSON rows), relative to the config.")),
+            "alert_threshold": optional(number("Metric value above which the widget alerts.")),
+            "alert_invert": boolean("Same as alert_mode invert.").with_default(false),
+            "alert_mode": optional(one_of(&["invert", "blink"], "How an alert shows.")),
+            "alert_blink_frames": integer("Frames between blink toggles.").with_default(15),
+            "wrap": boolean("command/media: wrap text instead of scrolling.").with_default(false),
+            "min": optional(number("Low end of the value range.")).with_default(0.0),
+            "max": optional(number("High end of the value range.")).with_default(100.0),
+            "max_bars": optional(integer("barchart: draw at most this many bars.")),
+        },
+    })
+}
+
+/// Keys in `config` that no schema property describes, as paths like
+/// `widgets[2].positon`. serde ignores them, so they are almost always typos.
+pub fn unknown_fields(config: &Value) -> Vec<String> {
+    let schema = config_schema();
+    let mut unknown = Vec::new();
+    collect_unknown(&schema, &schema, config, "", &mut unknown);
+    unknown
+}
+
+fn collect_unknown(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    out: &mut Vec<String>,
+) {
+    if let Some(target) = schema.get("$ref").and_then(Value::as_str) {
+        let name = target.trim_start_matches("#/$defs/");
+        if let Some(schema) = root["$defs"].get(name) {
+            collect_unknown(root, schema, value, path, out);
+        }
+        return;
+    }
+    if let Some(branches) = schema.get("anyOf").and_then(Value::as_array) {
+        for branch in branches {
+            collect_unknown(root, branch, value, path, out);
+        }
+        return;
+    }
+    match value {
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties");
+            if properties.is_none() && additional.is_none() {
+                return;
+            }
+            for (key, field) in fields {
+                let field_path =
+                    if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                match properties.and_then(|p| p.get(key)).or(additional) {
+                    Some(field_schema) => {
+                        collect_unknown(root, field_schema, field, &field_path, out)
+                    }
+                    None => out.push(field_path),
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    collect_unknown(root, item_schema, item, &format!("{path}[{index}]"), out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+trait WithDefault {
+    fn with_default(self, value: impl Into<Value>) -> Value;
+}
+
+impl WithDefault for Value {
+    fn with_default(mut self, value: impl Into<Value>) -> Value {
+        if let Some(object) = self.as_object_mut() {
+            object.insert("default".to_string(), value.into());
+        }
+        self
+    }
+}
+
+fn typed(kind: &str, description: &str) -> Value {
+    json!({ "type": kind, "description": description })
+}
+
+fn string(description: &str) -> Value {
+    typed("string", description)
+}
+
+fn integer(description: &str) -> Value {
+    typed("integer", description)
+}
+
+fn number(description: &str) -> Value {
+    typed("number", description)
+}
+
+fn boolean(description: &str) -> Value {
+    typed("boolean", description)
+}
+
+fn deprecated(description: &str) -> Value {
+    json!({ "type": "number", "deprecated": true, "description": description })
+}
+
+fn one_of(values: &[&str], description: &str) -> Value {
+    json!({ "type": "string", "enum": values, "description": description })
+}
+
+/// A number, or a hex string like `"0x1038"` / `"161C"`.
+fn hex(description: &str) -> Value {
+    json!({
+        "description": description,
+        "anyOf": [
+            { "type": "integer", "minimum": 0 },
+            { "type": "string", "pattern": "^(0[xX])?[0-9a-fA-F]+$" },
+            { "type": "null" },
+        ],
+    })
+}
+
+/// `schema`, or `null` (serde reads it as unset).
+fn optional(schema: Value) -> Value {
+    let mut out = Map::new();
+    let description = schema.get("description").cloned();
+    out.insert(
+        "anyOf".to_string(),
+        json!([without_description(schema), { "type": "null" }]),
+    );
+    if let Some(description) = description {
+        out.insert("description".to_string(), description);
+    }
+    Value::Object(out)
+}
+
+fn without_description(mut schema: Value) -> Value {
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("description");
+    }
+    schema
+}
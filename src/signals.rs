@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+// Net profile steps requested since the main loop last looked.
+static PROFILE_STEPS: AtomicIsize = AtomicIsize::new(0);
+
+extern "C" fn on_shutdown_signal(_: libc::c_int) {
+    SHUTDOWN.store(true, Ordering::SeqCst);
+}
+
+/// Routes SIGINT/SIGTERM to a flag polled by the main loop, so the loop can
+/// exit normally and let `Drop` impls (e.g. the parec monitor) run.
+pub fn install_shutdown_handler() {
+    let handler = on_shutdown_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN.load(Ordering::SeqCst)
+}
+
+extern "C" fn on_profile_signal(signal: libc::c_int) {
+    let step = if signal == libc::SIGUSR2 { -1 } else { 1 };
+    PROFILE_STEPS.fetch_add(step, Ordering::SeqCst);
+}
+
+/// SIGUSR1 asks for the next profile, SIGUSR2 for the previous one.
+pub fn install_profile_handler() {
+    let handler = on_profile_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler);
+        libc::signal(libc::SIGUSR2, handler);
+    }
+}
+
+/// Profile steps (positive = forward) requested since the last call.
+pub fn take_profile_steps() -> isize {
+    PROFILE_STEPS.swap(0, Ordering::SeqCst)
+}
@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Destination for packed frames: the keyboard, a file, the terminal, ...
+pub trait FrameSink {
+    fn send_frame(&mut self, frame: &[u8]) -> Result<()>;
+
+    /// Frames dropped because they matched the last one written.
+    fn skipped_frames(&self) -> u64 {
+        0
+    }
+
+    /// Appends the raw input reports the device sent since the last call,
+    /// without blocking. Sinks that can't receive input add nothing.
+    fn read_input(&mut self, _reports: &mut Vec<Vec<u8>>) {}
+}
+
+/// Appends every raw packed frame (no report header) to a file, which is
+/// truncated when the sink is created. Lets scripts consume frames
+/// without the keyboard attached.
+pub struct FileSink {
+    file: File,
+}
+
+impl FileSink {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("failed creating {}", path.display()))?;
+        Ok(Self { file })
+    }
+}
+
+impl FrameSink for FileSink {
+    fn send_frame(&mut self, frame: &[u8]) -> Result<()> {
+        self.file.write_all(frame)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Discards every frame. Stands in for the keyboard when the frame is only
+/// captured (`--png`, `--ascii`), so the device is never opened.
+pub struct NullSink;
+
+impl FrameSink for NullSink {
+    fn send_frame(&mut self, _frame: &[u8]) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+//! Minimal TOML reader for configs. Produces the same `serde_json::Value` a JSON
+//! config would, so `DashboardConfig` keeps one set of `Deserialize` impls.
+//!
+//! Covers what a hand-written config needs: comments, `key = value` with bare,
+//! quoted and dotted keys, `[tables]`, `[[arrays.of.tables]]`, basic and literal
+//! strings (including the `"""`/`'''` multi-line forms), integers (with `_`
+//! and `0x`/`0o`/`0b`), floats, booleans, arrays and inline tables. Dates
+//! aren't supported.
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Map, Number, Value};
+
+pub fn parse(text: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser
+        .document()
+        .map_err(|err| anyhow!("TOML line {}: {err}", parser.line))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn document(&mut self) -> Result<Value> {
+        let mut root = Map::new();
+        // Segments of the current `[table]`; array-of-tables segments resolve
+        // to their last element.
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_whitespace_and_comments();
+            let Some(c) = self.peek() else {
+                break;
+            };
+            if c == '[' {
+                self.pos += 1;
+                let array = self.eat('[');
+                self.skip_spaces();
+                let path = self.key()?;
+                self.skip_spaces();
+                self.expect(']')?;
+                if array {
+                    self.expect(']')?;
+                }
+                self.end_of_line()?;
+
+                let (last, parent) = path.split_last().expect("key() returns a segment");
+                let table = table_at(&mut root, parent)?;
+                if array {
+                    let entry = table
+                        .entry(last.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                    let Value::Array(items) = entry else {
+                        bail!("`{}` is not an array of tables", path.join("."));
+                    };
+                    items.push(Value::Object(Map::new()));
+                } else {
+                    match table.get(last) {
+                        Some(Value::Object(_)) | None => {
+                            table
+                                .entry(last.clone())
+                                .or_insert_with(|| Value::Object(Map::new()));
+                        }
+                        Some(_) => bail!("`{}` is already a value", path.join(".")),
+                    }
+                }
+                current = path;
+            } else {
+                let table = table_at(&mut root, &current)?;
+                self.key_value(table)?;
+                self.end_of_line()?;
+            }
+        }
+        Ok(Value::Object(root))
+    }
+
+    /// `key = value`, creating the tables of a dotted key inside `table`.
+    fn key_value(&mut self, table: &mut Map<String, Value>) -> Result<()> {
+        let path = self.key()?;
+        self.skip_spaces();
+        self.expect('=')?;
+        self.skip_spaces();
+        let value = self.value()?;
+
+        let (last, parent) = path.split_last().expect("key() returns a segment");
+        let target = table_at(table, parent)?;
+        if target.contains_key(last) {
+            bail!("duplicate key `{}`", path.join("."));
+        }
+        target.insert(last.clone(), value);
+        Ok(())
+    }
+
+    /// Dotted key: `a.b."c d"`.
+    fn key(&mut self) -> Result<Vec<String>> {
+        let mut segments = Vec::new();
+        loop {
+            self.skip_spaces();
+            let segment = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        bail!("expected a key");
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            segments.push(segment);
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t') if self.eat_word("true") => Ok(Value::Bool(true)),
+            Some('f') if self.eat_word("false") => Ok(Value::Bool(false)),
+            Some(c) if c == '+' || c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => bail!("unexpected `{c}` where a value should be"),
+            None => bail!("missing value"),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_whitespace_and_comments();
+            if !self.eat(',') {
+                self.skip_whitespace_and_comments();
+                self.expect(']')?;
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut table = Map::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Object(table));
+        }
+        loop {
+            self.key_value(&mut table)?;
+            self.skip_spaces();
+            if self.eat('}') {
+                return Ok(Value::Object(table));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-'))
+        {
+            self.pos += 1;
+        }
+        let raw: String = self.chars[start..self.pos]
+            .iter()
+            .filter(|&&c| c != '_')
+            .collect();
+
+        let radix = match raw.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            let value = u64::from_str_radix(&raw[2..], radix)
+                .map_err(|_| anyhow!("invalid number `{raw}`"))?;
+            return Ok(Value::Number(value.into()));
+        }
+        if let Ok(value) = raw.parse::<i64>() {
+            return Ok(Value::Number(value.into()));
+        }
+        raw.parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| anyhow!("invalid number `{raw}`"))
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        let multiline = self.eat_word("\"\"\"");
+        if !multiline {
+            self.expect('"')?;
+        } else {
+            self.eat_newline();
+        }
+
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.next() else {
+                bail!("unterminated string");
+            };
+            match c {
+                '"' if !multiline => return Ok(out),
+                '"' if self.eat_word("\"\"") => return Ok(out),
+                '\n' if !multiline => bail!("newline in string"),
+                '\\' => match self.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some(u @ ('u' | 'U')) => {
+                        let len = if u == 'u' { 4 } else { 8 };
+                        let digits: String = (0..len).filter_map(|_| self.next()).collect();
+                        let ch = u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| anyhow!("invalid escape \\{u}{digits}"))?;
+                        out.push(ch);
+                    }
+                    // Line-ending backslash: skip the newline and leading whitespace.
+                    Some(c) if multiline && c.is_whitespace() => {
+                        while self.peek().is_some_and(char::is_whitespace) {
+                            self.next();
+                        }
+                    }
+                    Some(c) => bail!("unknown escape \\{c}"),
+                    None => bail!("unterminated string"),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        let multiline = self.eat_word("'''");
+        if !multiline {
+            self.expect('\'')?;
+        } else {
+            self.eat_newline();
+        }
+
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                None => bail!("unterminated string"),
+                Some('\'') if !multiline => return Ok(out),
+                Some('\'') if self.eat_word("''") => return Ok(out),
+                Some('\n') if !multiline => bail!("newline in string"),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let len = word.chars().count();
+        let matches = self
+            .chars
+            .get(self.pos..self.pos + len)
+            .is_some_and(|s| s.iter().copied().eq(word.chars()));
+        if matches {
+            self.pos += len;
+        }
+        matches
+    }
+
+    fn eat_newline(&mut self) {
+        self.eat('\r');
+        self.eat('\n');
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(found) => bail!("expected `{c}`, found `{found}`"),
+                None => bail!("expected `{c}`, found end of file"),
+            }
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while self.peek().is_some_and(|c| c != '\n') {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if matches!(self.peek(), Some('\n' | '\r')) {
+                self.next();
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Only spaces and a comment may follow a statement on its line.
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => bail!("unexpected `{c}` after value"),
+        }
+    }
+}
+
+/// The table at `path` below `root`, creating missing ones. Arrays of tables
+/// resolve to their last element, as TOML headers do.
+fn table_at<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &[String],
+) -> Result<&'a mut Map<String, Value>> {
+    let mut table = root;
+    for segment in path {
+        let entry = table
+            .entry(segment.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let next = match entry {
+            Value::Array(items) => items.last_mut(),
+            other => Some(other),
+        };
+        match next {
+            Some(Value::Object(map)) => table = map,
+            _ => bail!("`{segment}` is not a table"),
+        }
+    }
+    Ok(table)
+}
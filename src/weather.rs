@@ -29,27 +29,44 @@ pub struct WeatherCache {
     pub condition: WeatherCondition,
     pub temperature: f32,
     shared: Arc<Mutex<WeatherData>>,
+    url: Option<String>,
     fetch_interval: Duration,
     retry_interval: Duration, // Wait before retrying after failure
 }
 
 impl WeatherCache {
     pub fn new() -> Self {
+        Self::with_source(None, None, Duration::from_secs(3600)) // 1 hour between successful fetches
+    }
+
+    /// Weather for a fixed `location` (skips the IP lookup) or from a custom
+    /// `url` returning Open-Meteo `current` JSON or wttr.in `?format=j1` JSON.
+    pub fn with_source(
+        location: Option<(f64, f64)>,
+        url: Option<String>,
+        fetch_interval: Duration,
+    ) -> Self {
         Self {
             condition: WeatherCondition::Unknown,
             temperature: 0.0,
             shared: Arc::new(Mutex::new(WeatherData {
                 condition: WeatherCondition::Unknown,
                 temperature: 0.0,
-                location: None,
+                location,
                 last_fetch_attempt: None,
                 fetch_in_progress: false,
             })),
-            fetch_interval: Duration::from_secs(3600), // 1 hour between successful fetches
-            retry_interval: Duration::from_secs(300),  // 5 minutes between retries on failure
+            url,
+            fetch_interval,
+            retry_interval: Duration::from_secs(300), // 5 minutes between retries on failure
         }
     }
 
+    /// Time between successful fetches; failures keep retrying every 5 minutes.
+    pub fn set_fetch_interval(&mut self, interval: Duration) {
+        self.fetch_interval = interval;
+    }
+
     /// Non-blocking update - spawns background thread if needed, reads cached value
     pub fn update(&mut self) {
         // First, read any updated data from background thread
@@ -65,7 +82,7 @@ impl WeatherCache {
                     false // Already fetching
                 } else if let Some(last) = data.last_fetch_attempt {
                     // Use retry_interval if we haven't successfully fetched yet
-                    let interval = if data.location.is_some() && data.condition != WeatherCondition::Unknown {
+                    let interval = if (data.location.is_some() || self.url.is_some()) && data.condition != WeatherCondition::Unknown {
                         self.fetch_interval
                     } else {
                         self.retry_interval
@@ -95,6 +112,20 @@ impl WeatherCache {
         }
 
         let shared = Arc::clone(&self.shared);
+        if let Some(url) = self.url.clone() {
+            thread::spawn(move || {
+                let result = fetch_json(&url).and_then(|json| parse_weather(&json));
+                if let Ok(mut data) = shared.lock() {
+                    data.fetch_in_progress = false;
+                    if let Some((condition, temperature)) = result {
+                        data.condition = condition;
+                        data.temperature = temperature;
+                    }
+                }
+            });
+            return;
+        }
+
         thread::spawn(move || {
             // Get location if needed
             let location = {
@@ -146,9 +177,12 @@ fn fetch_weather(lat: f64, lon: f64) -> Option<(WeatherCondition, f32)> {
         "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current=temperature_2m,weather_code",
         lat, lon
     );
+    parse_weather(&fetch_json(&url)?)
+}
 
+fn fetch_json(url: &str) -> Option<serde_json::Value> {
     let output = Command::new("curl")
-        .args(["-s", "--max-time", "5", &url])
+        .args(["-s", "--max-time", "5", url])
         .output()
         .ok()?;
 
@@ -156,14 +190,51 @@ fn fetch_weather(lat: f64, lon: f64) -> Option<(WeatherCondition, f32)> {
         return None;
     }
 
-    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
-    let current = json.get("current")?;
-    
-    let weather_code = current.get("weather_code")?.as_i64()? as i32;
-    let temperature = current.get("temperature_2m")?.as_f64()? as f32;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// Accepts Open-Meteo (`current.temperature_2m` + WMO `weather_code`) or
+/// wttr.in j1 (`current_condition[0].temp_C` + `weatherDesc`).
+fn parse_weather(json: &serde_json::Value) -> Option<(WeatherCondition, f32)> {
+    if let Some(current) = json.get("current") {
+        let weather_code = current.get("weather_code")?.as_i64()? as i32;
+        let temperature = current.get("temperature_2m")?.as_f64()? as f32;
+        return Some((weather_code_to_condition(weather_code), temperature));
+    }
+
+    let current = json.get("current_condition")?.get(0)?;
+    let temperature = current.get("temp_C")?.as_str()?.trim().parse::<f32>().ok()?;
+    let desc = current
+        .get("weatherDesc")
+        .and_then(|d| d.get(0))
+        .and_then(|d| d.get("value"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    Some((description_to_condition(desc), temperature))
+}
 
-    let condition = weather_code_to_condition(weather_code);
-    Some((condition, temperature))
+fn description_to_condition(desc: &str) -> WeatherCondition {
+    let desc = desc.to_ascii_lowercase();
+    // Order matters: "patchy light rain with thunder" is a thunderstorm.
+    if desc.contains("thunder") {
+        WeatherCondition::Thunderstorm
+    } else if desc.contains("snow") || desc.contains("sleet") || desc.contains("blizzard") || desc.contains("ice") {
+        WeatherCondition::Snow
+    } else if desc.contains("drizzle") {
+        WeatherCondition::Drizzle
+    } else if desc.contains("rain") || desc.contains("shower") {
+        WeatherCondition::Rain
+    } else if desc.contains("fog") || desc.contains("mist") {
+        WeatherCondition::Fog
+    } else if desc.contains("partly") {
+        WeatherCondition::PartlyCloudy
+    } else if desc.contains("cloud") || desc.contains("overcast") {
+        WeatherCondition::Cloudy
+    } else if desc.contains("sun") || desc.contains("clear") {
+        WeatherCondition::Sunny
+    } else {
+        WeatherCondition::Unknown
+    }
 }
 
 fn weather_code_to_condition(code: i32) -> WeatherCondition {